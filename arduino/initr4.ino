@@ -1,39 +1,239 @@
 #include <Servo.h>
 
-Servo servoX;
-Servo servoY;
-// upload 到 r4以后，要断电，要不然会报设备busy 
+// Desky 舵机固件，同时支持两种串口协议，格式说明见同目录下的 PROTOCOL.md
+//   1. 帧协议 v1：以 0xA5 开头，带序号、CRC 校验和 ACK/NACK 确认
+//   2. 旧版文本协议："x,y\n"，回复 "Position set to: x,y"
+// 收到 0xA5 时开始接收一帧，其余字节按文本行处理，因此旧版上位机无需改动即可继续使用
+
+// 帧格式: [0xA5][版本][序号][类型][长度][负载...][CRC16 高位][CRC16 低位]
+const uint8_t FRAME_START = 0xA5;
+const uint8_t PROTOCOL_VERSION = 1;
+const uint8_t MAX_PAYLOAD_LEN = 64;
+const uint8_t HEADER_LEN = 5;
+const uint8_t CRC_LEN = 2;
+
+// 帧类型
+const uint8_t KIND_HELLO = 0x01;
+const uint8_t KIND_IDENTIFY = 0x02;
+const uint8_t KIND_PING = 0x03;
+const uint8_t KIND_SET_POSITION = 0x10;
+const uint8_t KIND_ACK = 0x80;
+const uint8_t KIND_NACK = 0x81;
+
+// NACK 原因码
+const uint8_t NACK_BAD_CHECKSUM = 0x01;
+const uint8_t NACK_UNSUPPORTED = 0x02;
+const uint8_t NACK_INVALID_PAYLOAD = 0x03;
+
+// Identify 时报告的固件版本和型号
+const uint8_t FIRMWARE_VERSION[3] = {2, 0, 0};
+const char MODEL[] = "Desky R4";
+
+// 通道 0 为 X 轴(9 号引脚)，通道 1 为 Y 轴(10 号引脚)
+const uint8_t CHANNEL_COUNT = 2;
+const uint8_t SERVO_PINS[CHANNEL_COUNT] = {9, 10};
+const uint8_t MIN_ANGLE = 0;
+const uint8_t MAX_ANGLE = 180;
+const uint8_t CENTER_ANGLE = 90;
+
+// 一帧在该时间内没有收完时丢弃，避免残缺的帧吞掉后续数据
+const unsigned long FRAME_TIMEOUT_MS = 100;
+// 文本行的最大长度，超出时丢弃该行
+const unsigned int MAX_LINE_LEN = 32;
+
+Servo servos[CHANNEL_COUNT];
+
+// 正在接收的帧，frameLen 为 0 表示当前不在帧中
+uint8_t frame[HEADER_LEN + MAX_PAYLOAD_LEN + CRC_LEN];
+uint8_t frameLen = 0;
+unsigned long frameStarted = 0;
+
+// 正在接收的文本行，超长的行在遇到换行前一直丢弃
+String line;
+bool discardLine = false;
+
+// upload 到 r4以后，要断电，要不然会报设备busy
 void setup() {
   Serial.begin(9600);
-  servoX.attach(9);  // X轴舵机连接到9号引脚
-  servoY.attach(10); // Y轴舵机连接到10号引脚
-  
+  line.reserve(MAX_LINE_LEN);
+
   // 初始化舵机位置
-  servoX.write(90);
-  servoY.write(90);
+  for (uint8_t channel = 0; channel < CHANNEL_COUNT; channel++) {
+    servos[channel].attach(SERVO_PINS[channel]);
+    servos[channel].write(CENTER_ANGLE);
+  }
 }
 
 void loop() {
-  if (Serial.available() > 0) {
-    String input = Serial.readStringUntil('\n');
-    int commaIndex = input.indexOf(',');
-    
-    if (commaIndex != -1) {
-      int x = input.substring(0, commaIndex).toInt();
-      int y = input.substring(commaIndex + 1).toInt();
-      
-      // 确保值在有效范围内
-      x = constrain(x, 0, 180);
-      y = constrain(y, 0, 180);
-      
-      servoX.write(x);
-      servoY.write(y);
-      
-      // 发送确认信息回Node.js
-      Serial.print("Position set to: ");
-      Serial.print(x);
-      Serial.print(",");
-      Serial.println(y);
+  if (frameLen > 0 && millis() - frameStarted > FRAME_TIMEOUT_MS) {
+    frameLen = 0;
+  }
+
+  while (Serial.available() > 0) {
+    uint8_t value = Serial.read();
+    if (frameLen > 0) {
+      receiveFrameByte(value);
+    } else if (value == FRAME_START) {
+      frame[0] = value;
+      frameLen = 1;
+      frameStarted = millis();
+    } else {
+      receiveLineByte(value);
+    }
+  }
+}
+
+// CRC-16/CCITT-FALSE (多项式 0x1021，初始值 0xFFFF)，与上位机一致
+uint16_t crc16(const uint8_t *data, uint8_t length) {
+  uint16_t crc = 0xFFFF;
+  for (uint8_t i = 0; i < length; i++) {
+    crc ^= (uint16_t)data[i] << 8;
+    for (uint8_t bit = 0; bit < 8; bit++) {
+      crc = (crc & 0x8000) ? (crc << 1) ^ 0x1021 : crc << 1;
+    }
+  }
+  return crc;
+}
+
+void receiveFrameByte(uint8_t value) {
+  frame[frameLen++] = value;
+
+  // 长度字段不可信，说明这不是真正的帧头，丢弃后重新寻找起始字节
+  if (frameLen == HEADER_LEN && frame[4] > MAX_PAYLOAD_LEN) {
+    frameLen = 0;
+    return;
+  }
+  if (frameLen > HEADER_LEN && frameLen == HEADER_LEN + frame[4] + CRC_LEN) {
+    handleFrame();
+    frameLen = 0;
+  }
+}
+
+void sendFrame(uint8_t seq, uint8_t kind, const uint8_t *payload, uint8_t length) {
+  uint8_t bytes[HEADER_LEN + MAX_PAYLOAD_LEN + CRC_LEN];
+  bytes[0] = FRAME_START;
+  bytes[1] = PROTOCOL_VERSION;
+  bytes[2] = seq;
+  bytes[3] = kind;
+  bytes[4] = length;
+  memcpy(bytes + HEADER_LEN, payload, length);
+
+  uint16_t crc = crc16(bytes + 1, HEADER_LEN - 1 + length);
+  bytes[HEADER_LEN + length] = crc >> 8;
+  bytes[HEADER_LEN + length + 1] = crc & 0xFF;
+  Serial.write(bytes, HEADER_LEN + length + CRC_LEN);
+}
+
+void sendNack(uint8_t seq, uint8_t reason) {
+  sendFrame(seq, KIND_NACK, &reason, 1);
+}
+
+// 处理一个完整的帧，除主机发来的 ACK/NACK 外每帧都回复一个序号相同的 ACK 或 NACK
+//
+// 主机没收到 ACK 时会用同一序号重发，位置命令重复执行的结果相同，因此不需要去重
+void handleFrame() {
+  uint8_t seq = frame[2];
+  uint8_t kind = frame[3];
+  uint8_t length = frame[4];
+  uint8_t *payload = frame + HEADER_LEN;
+
+  uint16_t expected = crc16(frame + 1, HEADER_LEN - 1 + length);
+  uint16_t received = ((uint16_t)frame[HEADER_LEN + length] << 8) | frame[HEADER_LEN + length + 1];
+  if (expected != received) {
+    sendNack(seq, NACK_BAD_CHECKSUM);
+    return;
+  }
+
+  uint8_t reply[MAX_PAYLOAD_LEN];
+  uint8_t replyLen = 0;
+  switch (kind) {
+    case KIND_HELLO:
+      // 回复双方都支持的最高版本
+      reply[replyLen++] = length > 0 ? min(payload[0], PROTOCOL_VERSION) : PROTOCOL_VERSION;
+      break;
+
+    case KIND_IDENTIFY:
+      // [主版本][次版本][修订号][通道数][型号名称...]
+      memcpy(reply, FIRMWARE_VERSION, sizeof(FIRMWARE_VERSION));
+      replyLen = sizeof(FIRMWARE_VERSION);
+      reply[replyLen++] = CHANNEL_COUNT;
+      memcpy(reply + replyLen, MODEL, sizeof(MODEL) - 1);
+      replyLen += sizeof(MODEL) - 1;
+      break;
+
+    case KIND_PING:
+      break;
+
+    case KIND_SET_POSITION:
+      // 负载为若干 (通道, 角度) 字节对，回复限幅后实际采用的字节对
+      if (length == 0 || length % 2 != 0) {
+        sendNack(seq, NACK_INVALID_PAYLOAD);
+        return;
+      }
+      for (uint8_t i = 0; i < length; i += 2) {
+        if (payload[i] >= CHANNEL_COUNT) {
+          sendNack(seq, NACK_INVALID_PAYLOAD);
+          return;
+        }
+      }
+      for (uint8_t i = 0; i < length; i += 2) {
+        uint8_t angle = constrain(payload[i + 1], MIN_ANGLE, MAX_ANGLE);
+        servos[payload[i]].write(angle);
+        reply[replyLen++] = payload[i];
+        reply[replyLen++] = angle;
+      }
+      break;
+
+    case KIND_ACK:
+    case KIND_NACK:
+      return;
+
+    default:
+      sendNack(seq, NACK_UNSUPPORTED);
+      return;
+  }
+
+  sendFrame(seq, KIND_ACK, reply, replyLen);
+}
+
+// 旧版文本协议，逐字节接收 "x,y\n"
+void receiveLineByte(uint8_t value) {
+  if (value == '\r') {
+    return;
+  }
+  if (value != '\n') {
+    if (line.length() < MAX_LINE_LEN) {
+      line += (char)value;
+    } else {
+      // 过长的行不是有效命令，整行丢弃
+      line = "";
+      discardLine = true;
     }
+    return;
+  }
+  if (discardLine) {
+    discardLine = false;
+    line = "";
+    return;
   }
-}
\ No newline at end of file
+
+  int commaIndex = line.indexOf(',');
+  if (commaIndex != -1) {
+    int x = line.substring(0, commaIndex).toInt();
+    int y = line.substring(commaIndex + 1).toInt();
+
+    // 确保值在有效范围内
+    x = constrain(x, MIN_ANGLE, MAX_ANGLE);
+    y = constrain(y, MIN_ANGLE, MAX_ANGLE);
+
+    servos[0].write(x);
+    servos[1].write(y);
+
+    // 发送确认信息回上位机
+    Serial.print("Position set to: ");
+    Serial.print(x);
+    Serial.print(",");
+    Serial.println(y);
+  }
+  line = "";
+}
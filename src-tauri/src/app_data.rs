@@ -0,0 +1,113 @@
+use std::path::{Path, PathBuf};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use crate::commands::log_message;
+
+// 定义模块名称常量
+const MODEL_NAME: &str = "app_data";
+
+// 从应用数据目录中的 JSON 文件读取数据，文件不存在时返回默认值
+pub fn load_json<T: DeserializeOwned + Default>(path: &Path) -> Result<T, String> {
+    if !path.exists() {
+        return Ok(T::default());
+    }
+
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+// 将数据写入应用数据目录中的 JSON 文件，必要时创建目录
+pub fn save_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create directory {}: {}", parent.display(), e))?;
+    }
+
+    let content = serde_json::to_string_pretty(value)
+        .map_err(|e| format!("Failed to serialize {}: {}", path.display(), e))?;
+    std::fs::write(path, content)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+// 持久化到应用数据目录中单个 JSON 文件的数据
+//
+// 修改先在副本上进行，写入文件成功后才替换内存中的数据，保存失败时内存和文件保持一致；
+// 没有应用数据目录时数据只保存在内存中
+#[derive(Default)]
+pub struct JsonFile<T> {
+    path: Option<PathBuf>,
+    data: T,
+}
+
+impl<T: DeserializeOwned + Serialize + Default + Clone> JsonFile<T> {
+    // 从文件加载，文件不存在时从默认值开始
+    pub fn load(path: PathBuf) -> Result<Self, String> {
+        let data = load_json(&path)?;
+        Ok(JsonFile {
+            path: Some(path),
+            data,
+        })
+    }
+
+    pub fn data(&self) -> &T {
+        &self.data
+    }
+
+    // 在副本上执行修改并保存，description 用于没有应用数据目录时的提示
+    pub fn update<R>(&mut self, description: &str, change: impl FnOnce(&mut T) -> Result<R, String>) -> Result<R, String> {
+        let mut data = self.data.clone();
+        let result = change(&mut data)?;
+
+        match &self.path {
+            Some(path) => save_json(path, &data)?,
+            None => log_message(
+                format!("No app data directory available, {} kept in memory only", description),
+                "WARN".to_string(),
+                MODEL_NAME.to_string(),
+            ),
+        }
+
+        self.data = data;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn failed_saves_leave_the_data_unchanged() {
+        let dir = std::env::temp_dir().join(format!("desky-app-data-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("values.json");
+
+        let mut file: JsonFile<BTreeMap<String, u32>> = JsonFile::load(path.clone()).unwrap();
+        file.update("values", |values| {
+            values.insert("a".to_string(), 1);
+            Ok(())
+        }).unwrap();
+        assert_eq!(JsonFile::<BTreeMap<String, u32>>::load(path.clone()).unwrap().data().get("a"), Some(&1));
+
+        // 目标路径被目录占用，写入失败
+        std::fs::remove_file(&path).unwrap();
+        std::fs::create_dir(&path).unwrap();
+        let result = file.update("values", |values| {
+            values.insert("b".to_string(), 2);
+            Ok(())
+        });
+        assert!(result.is_err());
+        assert_eq!(file.data().get("b"), None);
+
+        let result: Result<(), String> = file.update("values", |values| {
+            values.clear();
+            Err("rejected".to_string())
+        });
+        assert!(result.is_err());
+        assert_eq!(file.data().len(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use crate::app_data::JsonFile;
+use crate::commands::log_message;
+
+// 定义模块名称常量
+const MODEL_NAME: &str = "calibration";
+// 校准数据在应用数据目录中的文件名
+pub const CALIBRATION_FILE: &str = "calibration.json";
+
+// 舵机能够接受的角度范围
+pub const SERVO_MIN: f64 = 0.0;
+pub const SERVO_MAX: f64 = 180.0;
+
+// 单个通道的校准参数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ChannelCalibration {
+    // 零点偏移(度)，在反向和查表之后叠加
+    pub offset: f64,
+    // 允许的最小和最大物理角度
+    pub min_angle: f64,
+    pub max_angle: f64,
+    // 是否反向安装
+    pub inverted: bool,
+    // 可选的非线性查表，每项为 [逻辑角度, 物理角度]，按逻辑角度升序排列
+    pub lookup_table: Option<Vec<[f64; 2]>>,
+}
+
+impl Default for ChannelCalibration {
+    fn default() -> Self {
+        ChannelCalibration {
+            offset: 0.0,
+            min_angle: SERVO_MIN,
+            max_angle: SERVO_MAX,
+            inverted: false,
+            lookup_table: None,
+        }
+    }
+}
+
+impl ChannelCalibration {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.offset.is_finite() {
+            return Err("Calibration offset must be a finite number".to_string());
+        }
+        if !(SERVO_MIN..=SERVO_MAX).contains(&self.min_angle)
+            || !(SERVO_MIN..=SERVO_MAX).contains(&self.max_angle)
+            || self.min_angle > self.max_angle
+        {
+            return Err(format!(
+                "Calibration limits must satisfy {} <= minAngle <= maxAngle <= {}",
+                SERVO_MIN, SERVO_MAX
+            ));
+        }
+        if let Some(table) = &self.lookup_table {
+            if table.len() < 2 {
+                return Err("Calibration lookup table needs at least two points".to_string());
+            }
+            if table.iter().flatten().any(|v| !v.is_finite()) {
+                return Err("Calibration lookup table contains invalid values".to_string());
+            }
+            if table.windows(2).any(|pair| pair[0][0] >= pair[1][0]) {
+                return Err("Calibration lookup table must be sorted by logical angle".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    // 将逻辑角度转换为实际下发给舵机的角度
+    pub fn apply(&self, angle: f64) -> Result<u8, String> {
+        if !angle.is_finite() {
+            return Err(format!("Invalid servo angle: {}", angle));
+        }
+
+        let mut value = if self.inverted { SERVO_MAX - angle } else { angle };
+        if let Some(table) = &self.lookup_table {
+            value = interpolate(table, value);
+        }
+        value += self.offset;
+
+        let value = value.clamp(self.min_angle, self.max_angle).clamp(SERVO_MIN, SERVO_MAX);
+        Ok(value.round() as u8)
+    }
+}
+
+// 分段线性插值，超出查表范围时使用端点的值
+fn interpolate(table: &[[f64; 2]], value: f64) -> f64 {
+    let (first, last) = match (table.first(), table.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return value,
+    };
+    if value <= first[0] {
+        return first[1];
+    }
+    if value >= last[0] {
+        return last[1];
+    }
+
+    for pair in table.windows(2) {
+        let ([x0, y0], [x1, y1]) = (pair[0], pair[1]);
+        if value <= x1 {
+            return y0 + (y1 - y0) * (value - x0) / (x1 - x0);
+        }
+    }
+    last[1]
+}
+
+// 所有设备的校准数据，按设备名称和通道名称索引，并持久化到应用数据目录
+#[derive(Default)]
+pub struct CalibrationStore {
+    devices: JsonFile<HashMap<String, HashMap<String, ChannelCalibration>>>,
+}
+
+impl CalibrationStore {
+    // 从文件加载校准数据，文件不存在时从空数据开始，其中有无效的校准参数时拒绝加载
+    pub fn load(path: PathBuf) -> Result<Self, String> {
+        let devices: JsonFile<HashMap<String, HashMap<String, ChannelCalibration>>> = JsonFile::load(path.clone())?;
+        for (device_name, channels) in devices.data() {
+            for (channel, calibration) in channels {
+                calibration.validate().map_err(|e| {
+                    format!("Invalid calibration for {} channel {} in {}: {}", device_name, channel, path.display(), e)
+                })?;
+            }
+        }
+
+        log_message(
+            format!("Loaded calibration for {} device(s) from {}", devices.data().len(), path.display()),
+            "INFO".to_string(),
+            MODEL_NAME.to_string(),
+        );
+
+        Ok(CalibrationStore { devices })
+    }
+
+    pub fn device(&self, device_name: &str) -> HashMap<String, ChannelCalibration> {
+        self.devices.data().get(device_name).cloned().unwrap_or_default()
+    }
+
+    pub fn channel(&self, device_name: &str, channel: &str) -> ChannelCalibration {
+        self.devices.data().get(device_name)
+            .and_then(|channels| channels.get(channel))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn set_channel(&mut self, device_name: &str, channel: &str, calibration: ChannelCalibration) -> Result<(), String> {
+        calibration.validate()?;
+        self.devices.update("calibration", |devices| {
+            devices.entry(device_name.to_string())
+                .or_default()
+                .insert(channel.to_string(), calibration);
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(points: &[[f64; 2]]) -> ChannelCalibration {
+        ChannelCalibration {
+            lookup_table: Some(points.to_vec()),
+            ..ChannelCalibration::default()
+        }
+    }
+
+    #[test]
+    fn default_calibration_rounds_and_clamps() {
+        let calibration = ChannelCalibration::default();
+        assert_eq!(calibration.apply(45.4), Ok(45));
+        assert_eq!(calibration.apply(-10.0), Ok(0));
+        assert_eq!(calibration.apply(200.0), Ok(180));
+        assert!(calibration.apply(f64::NAN).is_err());
+    }
+
+    #[test]
+    fn inversion_offset_and_limits_are_applied_in_order() {
+        let calibration = ChannelCalibration {
+            offset: 5.0,
+            min_angle: 20.0,
+            max_angle: 150.0,
+            inverted: true,
+            lookup_table: None,
+        };
+        assert_eq!(calibration.apply(100.0), Ok(85));
+        assert_eq!(calibration.apply(170.0), Ok(20));
+        assert_eq!(calibration.apply(0.0), Ok(150));
+    }
+
+    #[test]
+    fn lookup_table_interpolates_between_points() {
+        let calibration = table(&[[0.0, 10.0], [90.0, 100.0], [180.0, 170.0]]);
+        assert_eq!(calibration.apply(45.0), Ok(55));
+        assert_eq!(calibration.apply(135.0), Ok(135));
+        assert_eq!(calibration.apply(180.0), Ok(170));
+    }
+
+    #[test]
+    fn invalid_calibration_is_rejected() {
+        let inverted_limits = ChannelCalibration {
+            min_angle: 120.0,
+            max_angle: 60.0,
+            ..ChannelCalibration::default()
+        };
+        assert!(inverted_limits.validate().is_err());
+        assert!(table(&[[0.0, 0.0]]).validate().is_err());
+        assert!(table(&[[0.0, 0.0], [0.0, 90.0]]).validate().is_err());
+        assert!(table(&[[90.0, 0.0], [0.0, 90.0]]).validate().is_err());
+        assert!(table(&[[0.0, 0.0], [f64::NAN, 90.0]]).validate().is_err());
+        assert!(table(&[[0.0, 0.0], [180.0, 180.0]]).validate().is_ok());
+    }
+
+    #[test]
+    fn invalid_calibration_file_is_not_loaded() {
+        let dir = std::env::temp_dir().join(format!("desky-calibration-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(CALIBRATION_FILE);
+
+        std::fs::write(&path, r#"{"board": {"x": {"minAngle": 150, "maxAngle": 30}}}"#).unwrap();
+        assert!(CalibrationStore::load(path.clone()).err().unwrap().contains("board channel x"));
+
+        std::fs::write(&path, r#"{"board": {"x": {"offset": 3}}}"#).unwrap();
+        assert_eq!(CalibrationStore::load(path.clone()).unwrap().channel("board", "x").offset, 3.0);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
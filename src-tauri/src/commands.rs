@@ -3,21 +3,30 @@ use std::sync::Arc;
 use once_cell::sync::Lazy;
 
 // 引入本地模块
-use crate::device_manager::DeviceManager;
-use crate::http_client::HttpClient;
+use crate::calibration::ChannelCalibration;
+use crate::device_manager::{DeviceConfig, DeviceHealth, DeviceManager, LinkStats};
+use crate::device_state::DeviceState;
+use crate::discovery::DiscoveredPort;
+use crate::profiles::DeviceProfile;
+use crate::groups::{DeviceGroup, MemberResult};
+use crate::http_client::{HttpClient, ProxyResponse, ResponseType};
+use crate::http_stream;
+use crate::multipart::{FilePart, MultipartForm, TextField};
+use crate::motion_planner::MotionConfig;
+use crate::request_policy::{RequestPolicy, RequestPolicyOverride};
+use crate::safety::SafetyEnvelope;
+use crate::servo_controller::PositionAck;
 
 // 定义模块名称常量
 const MODEL_NAME: &str = "Commands";
 
 // 定义允许跨线程访问的状态结构体
+//
+// DeviceManager 只保存各设备 I/O 线程的句柄和共享状态，本身即满足 Send + Sync
 pub struct AppState {
     pub device_manager: Arc<DeviceManager>,
 }
 
-// 为 AppState 实现 Send 和 Sync trait
-unsafe impl Send for AppState {}
-unsafe impl Sync for AppState {}
-
 // 使用 Lazy 静态变量来存储 HTTP 客户端实例,确保只初始化一次
 static HTTP_CLIENT: Lazy<HttpClient> = Lazy::new(|| {
     log_message("Creating HTTP client instance".to_string(), "INFO".to_string(), MODEL_NAME.to_string());
@@ -31,8 +40,227 @@ pub async fn set_servo_position(
     device_name: String,
     x: Option<f64>,
     y: Option<f64>,
+    duration_ms: Option<u64>,
+    profile: Option<String>,
+) -> Result<Option<PositionAck>, String> {
+    state.device_manager.set_servo_position(device_name, x, y, duration_ms, profile).await
+}
+
+// 设置任意命名通道的命令处理函数，通道可以用名称或序号指定
+#[tauri::command]
+pub async fn set_servo_channels(
+    state: tauri::State<'_, AppState>,
+    device_name: String,
+    channels: std::collections::HashMap<String, f64>,
+    duration_ms: Option<u64>,
+    profile: Option<String>,
+) -> Result<Option<PositionAck>, String> {
+    state.device_manager.set_servo_channels(device_name, channels, duration_ms, profile).await
+}
+
+// 获取设备链路质量统计的命令处理函数
+#[tauri::command]
+pub fn get_link_stats(
+    state: tauri::State<'_, AppState>,
+    device_name: String,
+) -> Result<LinkStats, String> {
+    state.device_manager.get_link_stats(device_name)
+}
+
+// 获取设备校准参数的命令处理函数
+#[tauri::command]
+pub fn get_calibration(
+    state: tauri::State<'_, AppState>,
+    device_name: String,
+) -> Result<std::collections::HashMap<String, ChannelCalibration>, String> {
+    state.device_manager.get_calibration(device_name)
+}
+
+// 更新单个通道校准参数的命令处理函数
+#[tauri::command]
+pub fn set_calibration(
+    state: tauri::State<'_, AppState>,
+    device_name: String,
+    channel: String,
+    calibration: ChannelCalibration,
+) -> Result<(), String> {
+    state.device_manager.set_calibration(device_name, channel, calibration)
+}
+
+// 急停命令，取消所有设备上的运动并锁存
+#[tauri::command]
+pub fn emergency_stop(state: tauri::State<'_, AppState>) -> Result<(), String> {
+    state.device_manager.emergency_stop()
+}
+
+// 清除急停锁存的命令处理函数
+#[tauri::command]
+pub fn clear_emergency_stop(state: tauri::State<'_, AppState>) {
+    state.device_manager.clear_emergency_stop()
+}
+
+// 查询急停是否处于锁存状态的命令处理函数
+#[tauri::command]
+pub fn is_emergency_stopped(state: tauri::State<'_, AppState>) -> bool {
+    state.device_manager.is_emergency_stopped()
+}
+
+// 获取设备安全包络的命令处理函数
+#[tauri::command]
+pub fn get_safety_envelope(
+    state: tauri::State<'_, AppState>,
+    device_name: String,
+) -> Result<SafetyEnvelope, String> {
+    state.device_manager.get_safety_envelope(device_name)
+}
+
+// 更新设备安全包络的命令处理函数
+#[tauri::command]
+pub fn set_safety_envelope(
+    state: tauri::State<'_, AppState>,
+    device_name: String,
+    envelope: SafetyEnvelope,
+) -> Result<(), String> {
+    state.device_manager.set_safety_envelope(device_name, envelope)
+}
+
+// 在设备上播放手势的命令处理函数
+#[tauri::command]
+pub async fn play_gesture(
+    state: tauri::State<'_, AppState>,
+    device_name: String,
+    name: String,
+    intensity: Option<f64>,
+    speed: Option<f64>,
+) -> Result<(), String> {
+    state.device_manager.play_gesture(device_name, name, intensity, speed).await
+}
+
+// 列出可用手势的命令处理函数
+#[tauri::command]
+pub fn list_gestures(state: tauri::State<'_, AppState>) -> Result<Vec<String>, String> {
+    state.device_manager.list_gestures()
+}
+
+// 获取运动规划参数的命令处理函数
+#[tauri::command]
+pub fn get_motion_config(state: tauri::State<'_, AppState>) -> Result<MotionConfig, String> {
+    state.device_manager.get_motion_config()
+}
+
+// 更新运动规划参数的命令处理函数
+#[tauri::command]
+pub fn set_motion_config(state: tauri::State<'_, AppState>, config: MotionConfig) -> Result<(), String> {
+    state.device_manager.set_motion_config(config)
+}
+
+// 使用指定串口参数连接设备的命令处理函数
+#[tauri::command]
+pub async fn connect_device(
+    state: tauri::State<'_, AppState>,
+    device_name: String,
+    config: Option<DeviceConfig>,
 ) -> Result<(), String> {
-    state.device_manager.set_servo_position(device_name, x, y)
+    state.device_manager.connect_device(device_name, config).await
+}
+
+// 断开设备并释放端口的命令处理函数
+#[tauri::command]
+pub async fn disconnect_device(
+    state: tauri::State<'_, AppState>,
+    device_name: String,
+) -> Result<(), String> {
+    state.device_manager.disconnect_device(device_name).await
+}
+
+// 断开所有设备的命令处理函数
+#[tauri::command]
+pub async fn disconnect_all(state: tauri::State<'_, AppState>) -> Result<(), String> {
+    state.device_manager.disconnect_all().await
+}
+
+// 获取所有设备档案的命令处理函数
+#[tauri::command]
+pub fn get_profiles(state: tauri::State<'_, AppState>) -> Result<Vec<DeviceProfile>, String> {
+    state.device_manager.get_profiles()
+}
+
+// 新增或更新设备档案的命令处理函数
+#[tauri::command]
+pub fn set_profile(state: tauri::State<'_, AppState>, profile: DeviceProfile) -> Result<(), String> {
+    state.device_manager.set_profile(profile)
+}
+
+// 删除设备档案的命令处理函数，返回档案是否存在
+#[tauri::command]
+pub fn delete_profile(state: tauri::State<'_, AppState>, alias: String) -> Result<bool, String> {
+    state.device_manager.delete_profile(alias)
+}
+
+// 获取所有设备组的命令处理函数
+#[tauri::command]
+pub fn get_groups(state: tauri::State<'_, AppState>) -> Result<Vec<DeviceGroup>, String> {
+    state.device_manager.get_groups()
+}
+
+// 新增或更新设备组的命令处理函数
+#[tauri::command]
+pub fn set_group(state: tauri::State<'_, AppState>, name: String, members: Vec<String>) -> Result<(), String> {
+    state.device_manager.set_group(DeviceGroup { name, members })
+}
+
+// 删除设备组的命令处理函数，返回设备组是否存在
+#[tauri::command]
+pub fn delete_group(state: tauri::State<'_, AppState>, name: String) -> Result<bool, String> {
+    state.device_manager.delete_group(name)
+}
+
+// 同步设置组内所有设备姿态的命令处理函数
+#[tauri::command]
+pub async fn set_group_position(
+    state: tauri::State<'_, AppState>,
+    group: String,
+    channels: std::collections::HashMap<String, f64>,
+    duration_ms: Option<u64>,
+    profile: Option<String>,
+) -> Result<Vec<MemberResult>, String> {
+    state.device_manager.set_group_position(group, channels, duration_ms, profile).await
+}
+
+// 在组内所有设备上同步播放手势的命令处理函数
+#[tauri::command]
+pub async fn play_group_gesture(
+    state: tauri::State<'_, AppState>,
+    group: String,
+    name: String,
+    intensity: Option<f64>,
+    speed: Option<f64>,
+) -> Result<Vec<MemberResult>, String> {
+    state.device_manager.play_group_gesture(group, name, intensity, speed).await
+}
+
+// 获取设备状态的命令处理函数
+#[tauri::command]
+pub fn get_device_state(
+    state: tauri::State<'_, AppState>,
+    device_name: String,
+) -> Result<DeviceState, String> {
+    state.device_manager.get_device_state(device_name)
+}
+
+// 列出所有设备及其状态的命令处理函数
+#[tauri::command]
+pub fn list_devices(state: tauri::State<'_, AppState>) -> Result<Vec<DeviceState>, String> {
+    state.device_manager.list_devices()
+}
+
+// Ping 设备并返回往返时间的命令处理函数
+#[tauri::command]
+pub async fn ping_device(
+    state: tauri::State<'_, AppState>,
+    device_name: String,
+) -> Result<DeviceHealth, String> {
+    state.device_manager.ping_device(device_name).await
 }
 
 // 检查设备状态的命令处理函数
@@ -41,7 +269,7 @@ pub async fn check_device_status(
     state: tauri::State<'_, AppState>,
     device_name: String,
 ) -> Result<bool, String> {
-    state.device_manager.check_device_status(device_name)
+    state.device_manager.check_device_status(device_name).await
 }
 
 // 简单的问候命令示例
@@ -92,6 +320,15 @@ pub fn get_serial_ports() -> Result<Vec<String>, String> {
     }
 }
 
+// 发现设备的命令处理函数，返回串口的 USB 信息，probe 为 true 时识别 Desky 设备
+#[tauri::command]
+pub async fn discover_devices(
+    state: tauri::State<'_, AppState>,
+    probe: Option<bool>,
+) -> Result<Vec<DiscoveredPort>, String> {
+    state.device_manager.discover_devices(probe.unwrap_or(false)).await
+}
+
 // 代理HTTP请求的命令处理函数
 #[tauri::command]
 pub async fn proxy_request(
@@ -172,3 +409,107 @@ pub async fn proxy_request_with_headers(
             error_msg
         })
 }
+
+// 代理HTTP请求并返回状态码、响应头、最终地址、耗时和响应体的命令处理函数
+#[tauri::command]
+pub async fn proxy_fetch(
+    target_url: String,
+    method: String,
+    headers: Option<std::collections::HashMap<String, String>>,
+    body: Vec<u8>,
+    response_type: Option<ResponseType>,
+    policy: Option<RequestPolicyOverride>,
+) -> Result<ProxyResponse, String> {
+    let function_name = "proxy_fetch";
+    log_message(
+        format!("[{}] Received request for URL: {}", function_name, target_url),
+        "INFO".to_string(),
+        MODEL_NAME.to_string(),
+    );
+
+    let policy = HTTP_CLIENT.policy(policy)?;
+    HTTP_CLIENT.fetch(&target_url, &method, headers.unwrap_or_default(), body, response_type.unwrap_or_default(), &policy).await
+        .map_err(|e| {
+            let error_msg = format!("[{}] Request failed: {}", function_name, e);
+            log_message(
+                error_msg.clone(),
+                "ERROR".to_string(),
+                MODEL_NAME.to_string(),
+            );
+            error_msg
+        })
+}
+
+// 代理 multipart/form-data 请求的命令处理函数，由后端构建请求体，method 默认为 POST
+#[tauri::command]
+pub async fn proxy_multipart_request(
+    target_url: String,
+    method: Option<String>,
+    headers: Option<std::collections::HashMap<String, String>>,
+    fields: Vec<TextField>,
+    files: Vec<FilePart>,
+    response_type: Option<ResponseType>,
+    policy: Option<RequestPolicyOverride>,
+) -> Result<ProxyResponse, String> {
+    let function_name = "proxy_multipart_request";
+    log_message(
+        format!("[{}] Received request for URL: {} ({} field(s), {} file(s))", function_name, target_url, fields.len(), files.len()),
+        "INFO".to_string(),
+        MODEL_NAME.to_string(),
+    );
+
+    // 文件可能需要从本地读取，放到阻塞线程中构建
+    let form = tokio::task::spawn_blocking(move || MultipartForm::build(fields, files)).await
+        .map_err(|e| format!("[{}] Failed to build form: {}", function_name, e))??;
+    let method = method.unwrap_or_else(|| "POST".to_string());
+    let policy = HTTP_CLIENT.policy(policy)?;
+
+    HTTP_CLIENT.send_multipart(&target_url, &method, headers.unwrap_or_default(), form, response_type.unwrap_or_default(), &policy).await
+        .map_err(|e| {
+            let error_msg = format!("[{}] Request failed: {}", function_name, e);
+            log_message(
+                error_msg.clone(),
+                "ERROR".to_string(),
+                MODEL_NAME.to_string(),
+            );
+            error_msg
+        })
+}
+
+// 流式代理HTTP请求的命令处理函数，立即返回流编号，响应体通过 http-stream-* 事件发送
+#[tauri::command]
+pub async fn proxy_stream_request(
+    target_url: String,
+    method: String,
+    headers: Option<std::collections::HashMap<String, String>>,
+    body: Vec<u8>,
+    policy: Option<RequestPolicyOverride>,
+) -> Result<String, String> {
+    let function_name = "proxy_stream_request";
+    log_message(
+        format!("[{}] Received request for URL: {}", function_name, target_url),
+        "INFO".to_string(),
+        MODEL_NAME.to_string(),
+    );
+
+    let policy = HTTP_CLIENT.policy(policy)?;
+    http_stream::start_stream(&HTTP_CLIENT, target_url, method, headers.unwrap_or_default(), body, policy)
+}
+
+// 取消流式请求的命令处理函数，返回请求是否仍在进行
+#[tauri::command]
+pub fn cancel_stream_request(stream_id: String) -> Result<bool, String> {
+    http_stream::cancel_stream(&stream_id)
+}
+
+// 获取代理请求默认的超时和重试策略
+#[tauri::command]
+pub fn get_request_policy() -> Result<RequestPolicy, String> {
+    HTTP_CLIENT.default_policy()
+}
+
+// 设置代理请求默认的超时和重试策略，单个请求可通过 policy 参数覆盖其中的字段
+#[tauri::command]
+pub fn set_request_policy(policy: RequestPolicy) -> Result<(), String> {
+    HTTP_CLIENT.set_default_policy(policy)
+}
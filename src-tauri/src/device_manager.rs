@@ -1,94 +1,1668 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
-use crate::servo_controller::ServoController;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use tokio::time::MissedTickBehavior;
+use crate::calibration::{CalibrationStore, ChannelCalibration, CALIBRATION_FILE, SERVO_MAX, SERVO_MIN};
+use crate::safety::{SafetyEnvelope, SafetyStore, SAFETY_FILE};
+use crate::gesture::{Gesture, GestureLibrary, Keyframe, GESTURE_DIR, MAX_GESTURE_INTENSITY, MAX_GESTURE_SPEED, MIN_GESTURE_SPEED};
+use crate::discovery::{self, DiscoveredPort};
+use crate::device_state::{DeviceRecord, DeviceState};
+use crate::profiles::{DeviceProfile, ProfileStore, PROFILE_FILE};
+use crate::groups::{DeviceGroup, GroupStore, MemberResult, GROUP_FILE};
+use crate::events::{self, DEVICE_STATE};
+use crate::motion_planner::{MotionConfig, MotionProfile, Trajectory};
+use crate::reconnect::{ConnectionEvent, ConnectionState, ReconnectPolicy};
+use crate::servo_controller::{AckStatus, Identification, PingResult, PositionAck};
+use crate::servo_worker::ServoWorker;
 use crate::commands::log_message;
 
+// 固件上电后舵机所处的中间位置
+const CENTER_POSITION: f64 = 90.0;
+// 周期性发送设备状态事件的间隔
+const STATE_EVENT_INTERVAL: Duration = Duration::from_secs(1);
+// 组命令规划完成后到各成员同时开始运动的间隔，留出任务调度的时间
+const GROUP_START_LEAD: Duration = Duration::from_millis(50);
+// 通道序号在协议中占一个字节
+const MAX_CHANNELS: usize = u8::MAX as usize + 1;
+
+// 串口校验位
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Parity {
+    None,
+    Odd,
+    Even,
+}
+
+// 串口流控方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FlowControl {
+    None,
+    Software,
+    Hardware,
+}
+
+// 单个设备的连接参数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DeviceConfig {
+    pub baud_rate: u32,
+    pub data_bits: u8,
+    pub parity: Parity,
+    pub stop_bits: u8,
+    pub flow_control: FlowControl,
+    pub read_timeout_ms: u64,
+    pub write_timeout_ms: u64,
+    // 旧版文本协议每条命令结尾使用的换行符
+    pub line_terminator: String,
+    // 通道名称，下标即通道序号
+    pub channels: Vec<String>,
+    // 链路断开后的重连策略
+    pub reconnect: ReconnectPolicy,
+}
+
+impl Default for DeviceConfig {
+    fn default() -> Self {
+        DeviceConfig {
+            baud_rate: 9600,
+            data_bits: 8,
+            parity: Parity::None,
+            stop_bits: 1,
+            flow_control: FlowControl::None,
+            read_timeout_ms: 1000,
+            write_timeout_ms: 1000,
+            line_terminator: "\n".to_string(),
+            channels: vec!["x".to_string(), "y".to_string()],
+            reconnect: ReconnectPolicy::default(),
+        }
+    }
+}
+
+impl DeviceConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.channels.is_empty() || self.channels.len() > MAX_CHANNELS {
+            return Err(format!("A device needs between 1 and {} channels", MAX_CHANNELS));
+        }
+        for (index, name) in self.channels.iter().enumerate() {
+            if name.trim().is_empty() {
+                return Err(format!("Channel {} has an empty name", index));
+            }
+            if self.channels[..index].iter().any(|other| other.eq_ignore_ascii_case(name)) {
+                return Err(format!("Channel name {} is used more than once", name));
+            }
+        }
+        self.reconnect.validate()
+    }
+
+    // 将通道名称或序号解析为通道序号
+    pub fn resolve_channel(&self, key: &str) -> Result<usize, String> {
+        let index = match key.trim().parse::<usize>() {
+            Ok(index) => index,
+            Err(_) => self.channels.iter()
+                .position(|name| name.eq_ignore_ascii_case(key.trim()))
+                .ok_or_else(|| format!("Unknown servo channel: {}", key))?,
+        };
+
+        if index >= self.channels.len() {
+            return Err(format!("Servo channel {} out of range (device has {} channels)", index, self.channels.len()));
+        }
+        Ok(index)
+    }
+}
+
+// 健康检查的方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthCheckMode {
+    // 通过 Ping 帧确认设备响应
+    Ping,
+    // 旧版固件不支持 Ping，只检查端口是否打开
+    Passive,
+}
+
+// 设备健康检查结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceHealth {
+    pub online: bool,
+    pub mode: HealthCheckMode,
+    // Ping 的往返时间(毫秒)
+    pub rtt_ms: Option<f64>,
+    // 探测失败时的错误信息
+    pub error: Option<String>,
+}
+
+// 设备链路质量统计
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkStats {
+    // 下发的位置命令数
+    pub commands: u64,
+    // 确认角度与命令一致的次数
+    pub acked: u64,
+    // 确认角度与命令不一致(被固件限幅)的次数
+    pub mismatches: u64,
+    // 没有收到确认的次数
+    pub missing_acks: u64,
+    // 确认一致的命令所占比例，尚未下发命令时为 1
+    pub quality: f64,
+}
+
+impl LinkStats {
+    fn record(&mut self, ack: &PositionAck) {
+        self.commands += 1;
+        match ack.status {
+            AckStatus::Acked => self.acked += 1,
+            AckStatus::Clamped => self.mismatches += 1,
+            AckStatus::TimedOut => self.missing_acks += 1,
+        }
+        self.quality = self.acked as f64 / self.commands as f64;
+    }
+}
+
+// 已规划但尚未执行的运动，组命令据此在执行前统一各成员的时长
+struct PlannedMotion {
+    worker: ServoWorker,
+    trajectory: Trajectory,
+    // 本次运动涉及的通道序号
+    moving: Vec<usize>,
+    calibrations: Vec<ChannelCalibration>,
+    envelope: SafetyEnvelope,
+    config: MotionConfig,
+}
+
+// 组命令中每个成员的目标通道角度，目标无法计算的成员为错误
+type MemberTargets = Vec<(String, Result<HashMap<String, f64>, String>)>;
+// 组手势中仍在播放的成员：(名称, 配置, 起始姿态, 最近一次确认)
+type GestureMember = (String, DeviceConfig, Vec<f64>, Option<PositionAck>);
+
+// 所有字段都是 Arc，克隆出的实例共享同一份状态，供后台任务使用
+#[derive(Clone)]
 pub struct DeviceManager {
-    servo_controllers: Arc<Mutex<HashMap<String, ServoController>>>,
+    // 设备注册表只保存句柄，端口由各设备的 I/O 线程独占，一个设备阻塞不会影响其他设备
+    servo_workers: Arc<Mutex<HashMap<String, ServoWorker>>>,
+    device_configs: Arc<Mutex<HashMap<String, DeviceConfig>>>,
+    motion_config: Arc<Mutex<MotionConfig>>,
+    // 每个设备最近一次下发的姿态
+    poses: Arc<Mutex<HashMap<String, Vec<f64>>>>,
+    // 每个设备当前运动的代号，新的运动开始时递增，旧的运动据此退出
+    motion_generations: Arc<Mutex<HashMap<String, u64>>>,
+    calibration: Arc<Mutex<CalibrationStore>>,
+    link_stats: Arc<Mutex<HashMap<String, LinkStats>>>,
+    safety: Arc<Mutex<SafetyStore>>,
+    // 急停锁存标志，置位后拒绝所有运动直到被显式清除
+    emergency_stop: Arc<AtomicBool>,
+    gestures: Arc<Mutex<GestureLibrary>>,
+    // 正在后台重连的设备及对应重连任务的编号，移除后任务在下一次重试前退出
+    reconnecting: Arc<Mutex<HashMap<String, u64>>>,
+    next_reconnect_id: Arc<AtomicU64>,
+    // 每个设备的连接状态、命令统计和错误记录
+    device_records: Arc<Mutex<HashMap<String, DeviceRecord>>>,
+    // 带别名的设备档案，以别名作为设备名称时在打开端口前解析为实际端口
+    profiles: Arc<Mutex<ProfileStore>>,
+    // 命名的设备组，组命令同时下发给所有成员
+    groups: Arc<Mutex<GroupStore>>,
 }
 
 impl DeviceManager {
     pub fn new() -> Self {
         log_message("Creating new DeviceManager instance".to_string(), "INFO".to_string(), "DeviceManager".to_string());
         DeviceManager {
-            servo_controllers: Arc::new(Mutex::new(HashMap::new())),
+            servo_workers: Arc::new(Mutex::new(HashMap::new())),
+            device_configs: Arc::new(Mutex::new(HashMap::new())),
+            motion_config: Arc::new(Mutex::new(MotionConfig::default())),
+            poses: Arc::new(Mutex::new(HashMap::new())),
+            motion_generations: Arc::new(Mutex::new(HashMap::new())),
+            calibration: Arc::new(Mutex::new(CalibrationStore::default())),
+            link_stats: Arc::new(Mutex::new(HashMap::new())),
+            safety: Arc::new(Mutex::new(SafetyStore::default())),
+            emergency_stop: Arc::new(AtomicBool::new(false)),
+            gestures: Arc::new(Mutex::new(GestureLibrary::default())),
+            reconnecting: Arc::new(Mutex::new(HashMap::new())),
+            next_reconnect_id: Arc::new(AtomicU64::new(0)),
+            device_records: Arc::new(Mutex::new(HashMap::new())),
+            profiles: Arc::new(Mutex::new(ProfileStore::default())),
+            groups: Arc::new(Mutex::new(GroupStore::default())),
+        }
+    }
+
+    // 触发急停：取消所有设备上排队和正在执行的运动，并保持锁存直到被清除
+    pub fn emergency_stop(&self) -> Result<(), String> {
+        log_message("Emergency stop triggered".to_string(), "WARN".to_string(), "emergency_stop".to_string());
+        self.emergency_stop.store(true, Ordering::SeqCst);
+
+        // 递增所有设备的运动代号，正在执行的轨迹会在下一个周期退出
+        let mut generations = self.motion_generations.lock().map_err(|e| format!("Failed to lock motion generations: {}", e))?;
+        for generation in generations.values_mut() {
+            *generation += 1;
+        }
+        Ok(())
+    }
+
+    pub fn clear_emergency_stop(&self) {
+        log_message("Emergency stop cleared".to_string(), "INFO".to_string(), "emergency_stop".to_string());
+        self.emergency_stop.store(false, Ordering::SeqCst);
+    }
+
+    pub fn is_emergency_stopped(&self) -> bool {
+        self.emergency_stop.load(Ordering::SeqCst)
+    }
+
+    fn ensure_not_stopped(&self) -> Result<(), String> {
+        if self.is_emergency_stopped() {
+            return Err("Emergency stop is active, clear it before moving the servos".to_string());
         }
+        Ok(())
     }
 
-    pub fn set_servo_position(&self, device_name: String, x: Option<f64>, y: Option<f64>) -> Result<(), String> {
-        log_message(format!("Setting servo position for device: {}, X: {:?}, Y: {:?}", device_name, x, y), "INFO".to_string(), "set_servo_position".to_string());
-        
-        let mut servo_controllers = self.servo_controllers.lock().map_err(|e| {
-            let error_msg = format!("Failed to lock servo controllers: {}", e);
-            log_message(error_msg.clone(), "ERROR".to_string(), "set_servo_position".to_string());
+    fn lock_safety(&self, module: &str) -> Result<std::sync::MutexGuard<'_, SafetyStore>, String> {
+        self.safety.lock().map_err(|e| {
+            let error_msg = format!("Failed to lock safety envelopes: {}", e);
+            log_message(error_msg.clone(), "ERROR".to_string(), module.to_string());
             error_msg
-        })?;
-        
-        if !servo_controllers.contains_key(&device_name) {
-            log_message(format!("Creating new ServoController for device: {}", device_name), "INFO".to_string(), "set_servo_position".to_string());
-            let new_controller = ServoController::new(&device_name).map_err(|e| {
-                let error_msg = format!("Failed to create ServoController: {}", e);
-                log_message(error_msg.clone(), "ERROR".to_string(), "set_servo_position".to_string());
-                error_msg
-            })?;
-            servo_controllers.insert(device_name.clone(), new_controller);
+        })
+    }
+
+    pub fn get_safety_envelope(&self, device_name: String) -> Result<SafetyEnvelope, String> {
+        Ok(self.lock_safety("get_safety_envelope")?.envelope(&device_name))
+    }
+
+    // 更新并持久化设备的安全包络
+    pub fn set_safety_envelope(&self, device_name: String, envelope: SafetyEnvelope) -> Result<(), String> {
+        log_message(format!("Updating safety envelope for {}: {:?}", device_name, envelope), "INFO".to_string(), "set_safety_envelope".to_string());
+        self.lock_safety("set_safety_envelope")?
+            .set_envelope(&device_name, envelope)
+            .inspect_err(|e| {
+                log_message(e.clone(), "ERROR".to_string(), "set_safety_envelope".to_string());
+            })
+    }
+
+    fn lock_gestures(&self, module: &str) -> Result<std::sync::MutexGuard<'_, GestureLibrary>, String> {
+        self.gestures.lock().map_err(|e| {
+            let error_msg = format!("Failed to lock gesture library: {}", e);
+            log_message(error_msg.clone(), "ERROR".to_string(), module.to_string());
+            error_msg
+        })
+    }
+
+    // 列出所有可用的手势名称
+    pub fn list_gestures(&self) -> Result<Vec<String>, String> {
+        Ok(self.lock_gestures("list_gestures")?.names())
+    }
+
+    // 在设备上播放手势，关键帧偏移按 intensity 缩放，时长按 speed 缩短
+    //
+    // 手势以开始时的姿态为基准，被其他运动取代时停止播放剩余的关键帧
+    pub async fn play_gesture(
+        &self,
+        device_name: String,
+        name: String,
+        intensity: Option<f64>,
+        speed: Option<f64>,
+    ) -> Result<(), String> {
+        log_message(format!("Playing gesture {} on device: {}, intensity: {:?}, speed: {:?}", name, device_name, intensity, speed), "INFO".to_string(), "play_gesture".to_string());
+        self.ensure_not_stopped()?;
+
+        let gesture = self.gesture(&name, "play_gesture")?;
+        let (intensity, speed) = gesture_factors(intensity, speed)?;
+
+        let device_config = self.device_config(&device_name)?;
+        let origin = self.current_pose(&device_name, device_config.channels.len())?;
+
+        for (step, keyframe) in gesture.keyframes.iter().enumerate() {
+            let duration = keyframe_duration(keyframe, speed)?;
+            let generation = self.motion_generation(&device_name)?;
+
+            if keyframe.channels.is_empty() {
+                tokio::time::sleep(duration).await;
+                self.ensure_not_stopped()?;
+                if self.motion_generation(&device_name)? != generation {
+                    log_message(format!("Gesture {} on {} superseded during hold at keyframe {}", name, device_name, step), "INFO".to_string(), "play_gesture".to_string());
+                    return Ok(());
+                }
+                continue;
+            }
+
+            let channels = keyframe_targets(&device_config, &origin, keyframe, intensity)?;
+            self.move_channels(&device_name, channels, Some(duration.as_millis() as u64), keyframe.profile).await?;
+
+            // 本关键帧之外还有新的运动开始，说明手势已被取代
+            if self.motion_generation(&device_name)? != generation + 1 {
+                log_message(format!("Gesture {} on {} superseded at keyframe {}", name, device_name, step), "INFO".to_string(), "play_gesture".to_string());
+                return Ok(());
+            }
+        }
+
+        log_message(format!("Finished gesture {} on device: {}", name, device_name), "INFO".to_string(), "play_gesture".to_string());
+        Ok(())
+    }
+
+    fn gesture(&self, name: &str, module: &str) -> Result<Gesture, String> {
+        self.lock_gestures(module)?
+            .get(name)
+            .ok_or_else(|| format!("Unknown gesture: {}", name))
+    }
+
+    // 把同一个姿态同时下发给组内所有成员，返回每个成员的结果
+    //
+    // 各成员的运动时长统一为其中最慢的一个，并在同一时刻开始，因此会同时到达
+    pub async fn set_group_position(
+        &self,
+        group: String,
+        channels: HashMap<String, f64>,
+        duration_ms: Option<u64>,
+        profile: Option<String>,
+    ) -> Result<Vec<MemberResult>, String> {
+        log_message(format!("Setting group position for {}, channels: {:?}, duration: {:?}, profile: {:?}", group, channels, duration_ms, profile), "INFO".to_string(), "set_group_position".to_string());
+        self.ensure_not_stopped()?;
+        let members = self.group_members(&group, "set_group_position")?;
+        let profile = profile.as_deref().map(MotionProfile::parse).transpose()?;
+
+        let targets = members.into_iter().map(|member| (member, Ok(channels.clone()))).collect();
+        let results = self.dispatch_aligned(targets, duration_ms.map(Duration::from_millis), profile).await;
+        log_message(format!("Group position for {} finished: {:?}", group, results), "INFO".to_string(), "set_group_position".to_string());
+        Ok(results)
+    }
+
+    // 在组内所有成员上同步播放手势，每个关键帧都对齐开始时刻
+    //
+    // 每个成员以各自开始时的姿态为基准；出错或被其他运动取代的成员退出，其余成员继续播放
+    pub async fn play_group_gesture(
+        &self,
+        group: String,
+        name: String,
+        intensity: Option<f64>,
+        speed: Option<f64>,
+    ) -> Result<Vec<MemberResult>, String> {
+        log_message(format!("Playing gesture {} on group: {}, intensity: {:?}, speed: {:?}", name, group, intensity, speed), "INFO".to_string(), "play_group_gesture".to_string());
+        self.ensure_not_stopped()?;
+        let members = self.group_members(&group, "play_group_gesture")?;
+        let gesture = self.gesture(&name, "play_group_gesture")?;
+        let (intensity, speed) = gesture_factors(intensity, speed)?;
+
+        let mut active: Vec<GestureMember> = Vec::with_capacity(members.len());
+        let mut finished = Vec::new();
+        for member in members {
+            let origin = self.device_config(&member)
+                .and_then(|config| Ok((self.current_pose(&member, config.channels.len())?, config)));
+            match origin {
+                Ok((origin, config)) => active.push((member, config, origin, None)),
+                Err(e) => finished.push(MemberResult::new(&member, Err(e))),
+            }
+        }
+
+        for (step, keyframe) in gesture.keyframes.iter().enumerate() {
+            if active.is_empty() {
+                break;
+            }
+            let duration = keyframe_duration(keyframe, speed)?;
+
+            // 记录每个成员当前的运动代数，查询失败的成员单独报告错误
+            let mut tracked = Vec::with_capacity(active.len());
+            for entry in active {
+                match self.motion_generation(&entry.0) {
+                    Ok(generation) => tracked.push((entry, generation)),
+                    Err(e) => finished.push(MemberResult::new(&entry.0, Err(e))),
+                }
+            }
+
+            if keyframe.channels.is_empty() {
+                tokio::time::sleep(duration).await;
+                self.ensure_not_stopped()?;
+                // 停顿期间有新运动的成员已被取代
+                active = self.retain_unsuperseded(tracked, &name, step, &mut finished);
+                continue;
+            }
+
+            let targets = tracked.iter()
+                .map(|((member, config, origin, _), _)| (member.clone(), keyframe_targets(config, origin, keyframe, intensity)))
+                .collect();
+            let results = self.dispatch_aligned(targets, Some(duration), keyframe.profile).await;
+
+            let mut moved = Vec::with_capacity(tracked.len());
+            for (((member, config, origin, last_ack), generation), result) in tracked.into_iter().zip(results) {
+                if !result.success {
+                    finished.push(result);
+                    continue;
+                }
+                // 本关键帧自身的运动使代数加 1
+                let last_ack = result.ack.or(last_ack);
+                moved.push(((member, config, origin, last_ack), generation + 1));
+            }
+            active = self.retain_unsuperseded(moved, &name, step, &mut finished);
         }
 
-        let servo_controller = servo_controllers.get_mut(&device_name).ok_or_else(|| {
-            let error_msg = format!("Device not found: {}", device_name);
-            log_message(error_msg.clone(), "ERROR".to_string(), "set_servo_position".to_string());
+        finished.extend(active.into_iter().map(|(member, _, _, last_ack)| MemberResult::new(&member, Ok(last_ack))));
+        log_message(format!("Finished gesture {} on group {}: {:?}", name, group, finished), "INFO".to_string(), "play_group_gesture".to_string());
+        Ok(finished)
+    }
+
+    // 保留运动代数仍为预期值的成员，被其他运动取代或查询失败的成员移入 finished
+    fn retain_unsuperseded(
+        &self,
+        members: Vec<(GestureMember, u64)>,
+        gesture: &str,
+        step: usize,
+        finished: &mut Vec<MemberResult>,
+    ) -> Vec<GestureMember> {
+        let mut remaining = Vec::with_capacity(members.len());
+        for (entry, expected) in members {
+            match self.motion_generation(&entry.0) {
+                Ok(generation) if generation == expected => remaining.push(entry),
+                Ok(_) => {
+                    log_message(format!("Gesture {} on {} superseded at keyframe {}", gesture, entry.0, step), "INFO".to_string(), "play_group_gesture".to_string());
+                    finished.push(MemberResult::new(&entry.0, Ok(entry.3)));
+                }
+                Err(e) => finished.push(MemberResult::new(&entry.0, Err(e))),
+            }
+        }
+        remaining
+    }
+
+    // 规划每个成员的运动，把时长统一为最长的一个，然后在同一时刻开始执行
+    //
+    // 结果与 targets 的顺序一致，规划失败的成员直接报告错误，不影响其他成员
+    async fn dispatch_aligned(
+        &self,
+        targets: MemberTargets,
+        duration: Option<Duration>,
+        profile: Option<MotionProfile>,
+    ) -> Vec<MemberResult> {
+        let plans: Vec<_> = targets.into_iter()
+            .map(|(member, channels)| {
+                let plan = channels.and_then(|channels| self.plan_motion(&member, &channels, duration, profile));
+                (member, plan)
+            })
+            .collect();
+        let aligned = plans.iter()
+            .filter_map(|(_, plan)| plan.as_ref().ok()?.as_ref())
+            .map(|motion| motion.trajectory.duration())
+            .max()
+            .unwrap_or_default();
+        let start_at = tokio::time::Instant::now() + GROUP_START_LEAD;
+
+        let tasks: Vec<_> = plans.into_iter()
+            .map(|(member, plan)| {
+                let manager = self.clone();
+                let device_name = member.clone();
+                let task = tauri::async_runtime::spawn(async move {
+                    let Some(mut motion) = plan? else {
+                        return Ok(None);
+                    };
+                    motion.trajectory.stretch_to(aligned);
+                    tokio::time::sleep_until(start_at).await;
+                    manager.run_motion(&device_name, &motion).await
+                });
+                (member, task)
+            })
+            .collect();
+
+        let mut results = Vec::with_capacity(tasks.len());
+        for (member, task) in tasks {
+            let result = task.await.unwrap_or_else(|e| Err(format!("Group command task failed: {}", e)));
+            results.push(MemberResult::new(&member, result));
+        }
+        results
+    }
+
+    // 获取设备的链路质量统计
+    pub fn get_link_stats(&self, device_name: String) -> Result<LinkStats, String> {
+        let link_stats = self.link_stats.lock().map_err(|e| format!("Failed to lock link stats: {}", e))?;
+        Ok(link_stats.get(&device_name).cloned().unwrap_or(LinkStats {
+            quality: 1.0,
+            ..LinkStats::default()
+        }))
+    }
+
+    fn record_ack(&self, device_name: &str, ack: &PositionAck) -> Result<(), String> {
+        let mut link_stats = self.link_stats.lock().map_err(|e| format!("Failed to lock link stats: {}", e))?;
+        link_stats.entry(device_name.to_string()).or_default().record(ack);
+        Ok(())
+    }
+
+    // 设置应用数据目录并加载其中持久化的设备数据
+    //
+    // 每类数据单独加载，某个文件无效时保留内存中的数据且不会覆盖该文件，其余数据照常加载
+    pub fn set_data_dir(&self, data_dir: PathBuf) -> Result<(), String> {
+        log_message(format!("Using app data directory: {}", data_dir.display()), "INFO".to_string(), "DeviceManager".to_string());
+        let mut errors = Vec::new();
+        let mut record = |e: &String| {
+            log_message(e.clone(), "ERROR".to_string(), "DeviceManager".to_string());
+            errors.push(e.clone());
+        };
+
+        if let Ok(store) = CalibrationStore::load(data_dir.join(CALIBRATION_FILE)).inspect_err(&mut record) {
+            *self.lock_calibration("DeviceManager")? = store;
+        }
+        if let Ok(store) = SafetyStore::load(data_dir.join(SAFETY_FILE)).inspect_err(&mut record) {
+            *self.lock_safety("DeviceManager")? = store;
+        }
+        if let Ok(library) = GestureLibrary::load(data_dir.join(GESTURE_DIR)).inspect_err(&mut record) {
+            *self.lock_gestures("DeviceManager")? = library;
+        }
+        if let Ok(store) = ProfileStore::load(data_dir.join(PROFILE_FILE)).inspect_err(&mut record) {
+            *self.lock_profiles("DeviceManager")? = store;
+        }
+        if let Ok(store) = GroupStore::load(data_dir.join(GROUP_FILE)).inspect_err(&mut record) {
+            *self.lock_groups("DeviceManager")? = store;
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("; "))
+        }
+    }
+
+    fn lock_profiles(&self, module: &str) -> Result<std::sync::MutexGuard<'_, ProfileStore>, String> {
+        self.profiles.lock().map_err(|e| {
+            let error_msg = format!("Failed to lock device profiles: {}", e);
+            log_message(error_msg.clone(), "ERROR".to_string(), module.to_string());
+            error_msg
+        })
+    }
+
+    pub fn get_profiles(&self) -> Result<Vec<DeviceProfile>, String> {
+        Ok(self.lock_profiles("get_profiles")?.list())
+    }
+
+    // 新增或更新并持久化设备档案
+    pub fn set_profile(&self, profile: DeviceProfile) -> Result<(), String> {
+        log_message(format!("Saving device profile: {:?}", profile), "INFO".to_string(), "set_profile".to_string());
+        self.lock_profiles("set_profile")?
+            .set(profile)
+            .inspect_err(|e| {
+                log_message(e.clone(), "ERROR".to_string(), "set_profile".to_string());
+            })
+    }
+
+    pub fn delete_profile(&self, alias: String) -> Result<bool, String> {
+        log_message(format!("Deleting device profile: {}", alias), "INFO".to_string(), "delete_profile".to_string());
+        self.lock_profiles("delete_profile")?.remove(&alias)
+    }
+
+    fn lock_groups(&self, module: &str) -> Result<std::sync::MutexGuard<'_, GroupStore>, String> {
+        self.groups.lock().map_err(|e| {
+            let error_msg = format!("Failed to lock device groups: {}", e);
+            log_message(error_msg.clone(), "ERROR".to_string(), module.to_string());
+            error_msg
+        })
+    }
+
+    pub fn get_groups(&self) -> Result<Vec<DeviceGroup>, String> {
+        Ok(self.lock_groups("get_groups")?.list())
+    }
+
+    // 新增或更新并持久化设备组
+    pub fn set_group(&self, group: DeviceGroup) -> Result<(), String> {
+        log_message(format!("Saving device group: {:?}", group), "INFO".to_string(), "set_group".to_string());
+        self.lock_groups("set_group")?
+            .set(group)
+            .inspect_err(|e| {
+                log_message(e.clone(), "ERROR".to_string(), "set_group".to_string());
+            })
+    }
+
+    pub fn delete_group(&self, name: String) -> Result<bool, String> {
+        log_message(format!("Deleting device group: {}", name), "INFO".to_string(), "delete_group".to_string());
+        self.lock_groups("delete_group")?.remove(&name)
+    }
+
+    fn group_members(&self, name: &str, module: &str) -> Result<Vec<String>, String> {
+        self.lock_groups(module)?
+            .get(name)
+            .map(|group| group.members.clone())
+            .ok_or_else(|| format!("Unknown device group: {}", name))
+    }
+
+    // 把设备名称解析为要打开的端口，别名按档案的匹配规则查找当前的串口，其他名称原样使用
+    fn resolve_port(&self, device_name: &str) -> Result<String, String> {
+        let match_rule = match self.lock_profiles("DeviceManager")?.get(device_name) {
+            Some(profile) => profile.match_rule.clone(),
+            None => return Ok(device_name.to_string()),
+        };
+        if let Some(path) = match_rule.path_only() {
+            return Ok(path.to_string());
+        }
+
+        let port_name = discovery::list_ports()?
+            .into_iter()
+            .find(|port| match_rule.matches(port))
+            .map(|port| port.port_name)
+            .ok_or_else(|| format!("No serial port matches device profile {}", device_name))?;
+        log_message(format!("Resolved device {} to port {}", device_name, port_name), "INFO".to_string(), "DeviceManager".to_string());
+        Ok(port_name)
+    }
+
+    fn lock_calibration(&self, module: &str) -> Result<std::sync::MutexGuard<'_, CalibrationStore>, String> {
+        self.calibration.lock().map_err(|e| {
+            let error_msg = format!("Failed to lock calibration: {}", e);
+            log_message(error_msg.clone(), "ERROR".to_string(), module.to_string());
+            error_msg
+        })
+    }
+
+    // 获取设备各通道的校准参数，按通道名称索引
+    //
+    // 有档案的设备使用档案中的校准参数
+    pub fn get_calibration(&self, device_name: String) -> Result<HashMap<String, ChannelCalibration>, String> {
+        if let Some(profile) = self.lock_profiles("get_calibration")?.get(&device_name) {
+            return Ok(profile.calibration.clone());
+        }
+        Ok(self.lock_calibration("get_calibration")?.device(&device_name))
+    }
+
+    // 更新并持久化单个通道的校准参数，通道可以用名称或序号指定
+    pub fn set_calibration(&self, device_name: String, channel: String, calibration: ChannelCalibration) -> Result<(), String> {
+        let device_config = self.device_config(&device_name)?;
+        let index = device_config.resolve_channel(&channel)?;
+        let channel_name = &device_config.channels[index];
+        log_message(format!("Updating calibration for {} channel {}: {:?}", device_name, channel_name, calibration), "INFO".to_string(), "set_calibration".to_string());
+
+        let mut profiles = self.lock_profiles("set_calibration")?;
+        let result = if profiles.get(&device_name).is_some() {
+            profiles.set_calibration(&device_name, channel_name, calibration)
+        } else {
+            self.lock_calibration("set_calibration")?.set_channel(&device_name, channel_name, calibration)
+        };
+        result.inspect_err(|e| {
+            log_message(e.clone(), "ERROR".to_string(), "set_calibration".to_string());
+        })
+    }
+
+    // 按通道序号排列的校准参数
+    fn channel_calibrations(&self, device_name: &str, device_config: &DeviceConfig) -> Result<Vec<ChannelCalibration>, String> {
+        if let Some(profile) = self.lock_profiles("DeviceManager")?.get(device_name) {
+            return Ok(device_config.channels.iter()
+                .map(|channel| profile.calibration.get(channel).cloned().unwrap_or_default())
+                .collect());
+        }
+
+        let store = self.lock_calibration("DeviceManager")?;
+        Ok(device_config.channels.iter()
+            .map(|channel| store.channel(device_name, channel))
+            .collect())
+    }
+
+    pub fn get_motion_config(&self) -> Result<MotionConfig, String> {
+        let motion_config = self.motion_config.lock().map_err(|e| {
+            let error_msg = format!("Failed to lock motion config: {}", e);
+            log_message(error_msg.clone(), "ERROR".to_string(), "get_motion_config".to_string());
             error_msg
         })?;
-        
-        let x = x.map(|v| v as u8);
-        let y = y.map(|v| v as u8);
+        Ok(motion_config.clone())
+    }
 
-        servo_controller.set_position(x, y).map_err(|e| {
-            let error_msg = format!("Failed to set servo position: {}", e);
-            log_message(error_msg.clone(), "ERROR".to_string(), "set_servo_position".to_string());
+    pub fn set_motion_config(&self, config: MotionConfig) -> Result<(), String> {
+        config.validate()?;
+        log_message(format!("Updating motion config: {:?}", config), "INFO".to_string(), "set_motion_config".to_string());
+        *self.motion_config.lock().map_err(|e| {
+            let error_msg = format!("Failed to lock motion config: {}", e);
+            log_message(error_msg.clone(), "ERROR".to_string(), "set_motion_config".to_string());
             error_msg
+        })? = config;
+        Ok(())
+    }
+
+    // 使用指定参数连接设备，已有的连接会被关闭并按新参数重新打开
+    //
+    // 未指定参数时沿用上一次的参数，别名设备使用档案中的参数
+    pub async fn connect_device(&self, device_name: String, config: Option<DeviceConfig>) -> Result<(), String> {
+        let config = match config {
+            Some(config) => config,
+            None => self.device_config(&device_name)?,
+        };
+        log_message(format!("Connecting device {} with config: {:?}", device_name, config), "INFO".to_string(), "connect_device".to_string());
+        config.validate()?;
+        let port_name = self.resolve_port(&device_name).inspect_err(|e| {
+            log_message(e.clone(), "ERROR".to_string(), "connect_device".to_string());
         })?;
 
-        log_message(format!("Successfully set servo position for device: {}", device_name), "INFO".to_string(), "set_servo_position".to_string());
+        // 显式连接取代后台重连；先释放旧的端口，否则串口会因为被占用而无法重新打开
+        self.lock_reconnecting("connect_device")?.remove(&device_name);
+        self.close_worker(&device_name, "connect_device").await?;
+
+        let worker = ServoWorker::spawn(&port_name, &config, self.emergency_stop.clone()).await.inspect_err(|e| {
+            log_message(e.clone(), "ERROR".to_string(), "connect_device".to_string());
+        })?;
+        self.lock_workers("connect_device")?.insert(device_name.clone(), worker);
+
+        self.device_configs.lock().map_err(|e| {
+            let error_msg = format!("Failed to lock device configs: {}", e);
+            log_message(error_msg.clone(), "ERROR".to_string(), "connect_device".to_string());
+            error_msg
+        })?.insert(device_name.clone(), config);
+
+        log_message(format!("Successfully connected device: {}", device_name), "INFO".to_string(), "connect_device".to_string());
+        self.publish_connection(ConnectionEvent::new(&device_name, ConnectionState::Connected));
         Ok(())
     }
 
-    pub fn check_device_status(&self, device_name: String) -> Result<bool, String> {
-        log_message(format!("Checking device status for: {}", device_name), "INFO".to_string(), "check_device_status".to_string());
-        
-        let mut servo_controllers = self.servo_controllers.lock().map_err(|e| {
-            let error_msg = format!("Failed to lock servo controllers: {}", e);
-            log_message(error_msg.clone(), "ERROR".to_string(), "check_device_status".to_string());
+    // 获取设备的连接参数，未连接过的设备使用档案中的参数或默认值
+    fn device_config(&self, device_name: &str) -> Result<DeviceConfig, String> {
+        let device_configs = self.device_configs.lock().map_err(|e| {
+            let error_msg = format!("Failed to lock device configs: {}", e);
+            log_message(error_msg.clone(), "ERROR".to_string(), "DeviceManager".to_string());
             error_msg
         })?;
-        
-        if !servo_controllers.contains_key(&device_name) {
-            log_message(format!("Creating new ServoController for device: {}", device_name), "INFO".to_string(), "check_device_status".to_string());
-            let new_controller = ServoController::new(&device_name).map_err(|e| {
-                let error_msg = format!("Failed to create ServoController: {}", e);
-                log_message(error_msg.clone(), "ERROR".to_string(), "check_device_status".to_string());
-                error_msg
+        if let Some(config) = device_configs.get(device_name) {
+            return Ok(config.clone());
+        }
+        drop(device_configs);
+
+        Ok(self.lock_profiles("DeviceManager")?
+            .get(device_name)
+            .map(|profile| profile.config.clone())
+            .unwrap_or_default())
+    }
+
+    fn lock_workers(&self, module: &str) -> Result<std::sync::MutexGuard<'_, HashMap<String, ServoWorker>>, String> {
+        self.servo_workers.lock().map_err(|e| {
+            let error_msg = format!("Failed to lock servo workers: {}", e);
+            log_message(error_msg.clone(), "ERROR".to_string(), module.to_string());
+            error_msg
+        })
+    }
+
+    // 断开设备：取消重连和正在执行的运动，关闭端口后返回
+    pub async fn disconnect_device(&self, device_name: String) -> Result<(), String> {
+        log_message(format!("Disconnecting device: {}", device_name), "INFO".to_string(), "disconnect_device".to_string());
+
+        self.lock_reconnecting("disconnect_device")?.remove(&device_name);
+        self.begin_motion(&device_name)?;
+        self.close_worker(&device_name, "disconnect_device").await?;
+
+        self.publish_connection(ConnectionEvent::new(&device_name, ConnectionState::Disconnected));
+        log_message(format!("Device {} disconnected", device_name), "INFO".to_string(), "disconnect_device".to_string());
+        Ok(())
+    }
+
+    // 断开所有已连接或正在重连的设备
+    pub async fn disconnect_all(&self) -> Result<(), String> {
+        let mut names: BTreeSet<String> = self.lock_workers("disconnect_all")?.keys().cloned().collect();
+        names.extend(self.lock_reconnecting("disconnect_all")?.keys().cloned());
+        log_message(format!("Disconnecting all devices: {:?}", names), "INFO".to_string(), "disconnect_all".to_string());
+
+        for device_name in names {
+            self.disconnect_device(device_name).await?;
+        }
+        Ok(())
+    }
+
+    // 从注册表中移除设备的句柄并等待 I/O 线程释放端口
+    async fn close_worker(&self, device_name: &str, module: &str) -> Result<(), String> {
+        let worker = self.lock_workers(module)?.remove(device_name);
+        if let Some(worker) = worker {
+            worker.close().await.inspect_err(|e| {
+                log_message(e.clone(), "ERROR".to_string(), module.to_string());
             })?;
-            servo_controllers.insert(device_name.clone(), new_controller);
+            log_message(format!("Closed existing ServoController for device: {}", device_name), "INFO".to_string(), module.to_string());
         }
+        Ok(())
+    }
 
-        let servo_controller = servo_controllers.get_mut(&device_name).ok_or_else(|| {
-            let error_msg = format!("Device not found: {}", device_name);
-            log_message(error_msg.clone(), "ERROR".to_string(), "check_device_status".to_string());
+    fn lock_reconnecting(&self, module: &str) -> Result<std::sync::MutexGuard<'_, HashMap<String, u64>>, String> {
+        self.reconnecting.lock().map_err(|e| {
+            let error_msg = format!("Failed to lock reconnect state: {}", e);
+            log_message(error_msg.clone(), "ERROR".to_string(), module.to_string());
+            error_msg
+        })
+    }
+
+    // 串口被拔出：关闭端口并在后台等待设备重新出现，返回拔出前设备是否处于打开状态
+    //
+    // 别名设备打开的是解析后的端口，因此按句柄的端口名称查找
+    pub fn mark_detached(&self, port_name: &str) -> Result<bool, String> {
+        let detached: Vec<String> = {
+            let mut workers = self.lock_workers("hotplug")?;
+            let names: Vec<String> = workers.iter()
+                .filter(|(_, worker)| worker.port_name() == port_name)
+                .map(|(name, _)| name.clone())
+                .collect();
+            for name in &names {
+                workers.remove(name);
+            }
+            names
+        };
+
+        for device_name in &detached {
+            log_message(format!("Closed ServoController for detached device: {} ({})", device_name, port_name), "WARN".to_string(), "hotplug".to_string());
+            self.start_reconnect(device_name, "Device detached".to_string());
+        }
+        Ok(!detached.is_empty())
+    }
+
+    // 获取已连接设备的 I/O 句柄，设备需要先通过 connect_device 打开
+    //
+    // 锁只在查找句柄时短暂持有，端口 I/O 期间不会阻塞其他设备
+    fn worker(&self, device_name: &str, module: &str) -> Result<ServoWorker, String> {
+        if let Some(worker) = self.lock_workers(module)?.get(device_name) {
+            return Ok(worker.clone());
+        }
+
+        let error_msg = if self.lock_reconnecting(module)?.contains_key(device_name) {
+            format!("Device {} is reconnecting", device_name)
+        } else {
+            format!("Device {} is not connected, call connect_device first", device_name)
+        };
+        log_message(error_msg.clone(), "WARN".to_string(), module.to_string());
+        Err(error_msg)
+    }
+
+    // 下发位置命令并记录统计，I/O 线程因链路错误退出时启动重连
+    async fn send_channels(&self, device_name: &str, worker: &ServoWorker, channels: Vec<(u8, u8)>) -> Result<PositionAck, String> {
+        let started = Instant::now();
+        let result = worker.set_channels(channels).await;
+        let latency = started.elapsed();
+
+        self.update_record(device_name, |record| record.record_command(&result, latency));
+        match &result {
+            Ok(ack) => self.record_ack(device_name, ack)?,
+            Err(e) => self.check_link(device_name, worker, e),
+        }
+        result
+    }
+
+    fn update_record(&self, device_name: &str, update: impl FnOnce(&mut DeviceRecord)) {
+        match self.device_records.lock() {
+            Ok(mut records) => update(records.entry(device_name.to_string()).or_default()),
+            Err(e) => log_message(format!("Failed to lock device records: {}", e), "ERROR".to_string(), "DeviceManager".to_string()),
+        }
+    }
+
+    // 记录设备的连接状态并通知前端
+    fn publish_connection(&self, event: ConnectionEvent) {
+        self.update_record(&event.device_name, |record| record.set_connection(event.state));
+        event.emit();
+    }
+
+    // 获取设备当前的状态
+    pub fn get_device_state(&self, device_name: String) -> Result<DeviceState, String> {
+        let device_config = self.device_config(&device_name)?;
+        let pose = self.poses.lock()
+            .map_err(|e| format!("Failed to lock poses: {}", e))?
+            .get(&device_name)
+            .cloned()
+            .unwrap_or_default();
+        let link = self.get_link_stats(device_name.clone())?;
+
+        let records = self.device_records.lock().map_err(|e| {
+            let error_msg = format!("Failed to lock device records: {}", e);
+            log_message(error_msg.clone(), "ERROR".to_string(), "get_device_state".to_string());
             error_msg
         })?;
-        
-        log_message("Attempting to set test position to check device status".to_string(), "INFO".to_string(), "check_device_status".to_string());
-        match servo_controller.set_position(Some(90), None) {
-            Ok(_) => {
-                log_message(format!("Device {} is online and responsive", device_name), "INFO".to_string(), "check_device_status".to_string());
-                Ok(true)
+        let state = match records.get(&device_name) {
+            Some(record) => record.snapshot(&device_name, &device_config.channels, &pose, link),
+            None => DeviceRecord::default().snapshot(&device_name, &device_config.channels, &pose, link),
+        };
+        Ok(state)
+    }
+
+    // 列出所有打开过或配置过的设备及其状态
+    pub fn list_devices(&self) -> Result<Vec<DeviceState>, String> {
+        let mut names: BTreeSet<String> = self.lock_workers("list_devices")?.keys().cloned().collect();
+        names.extend(self.device_configs.lock().map_err(|e| format!("Failed to lock device configs: {}", e))?.keys().cloned());
+        names.extend(self.device_records.lock().map_err(|e| format!("Failed to lock device records: {}", e))?.keys().cloned());
+
+        names.into_iter().map(|name| self.get_device_state(name)).collect()
+    }
+
+    // 启动后台任务，定期向前端发送所有设备的状态
+    pub fn spawn_state_events(&self) {
+        let manager = self.clone();
+        tauri::async_runtime::spawn(async move {
+            let mut ticker = tokio::time::interval(STATE_EVENT_INTERVAL);
+            loop {
+                ticker.tick().await;
+                match manager.list_devices() {
+                    Ok(devices) if !devices.is_empty() => events::emit(DEVICE_STATE, devices),
+                    Ok(_) => {}
+                    Err(e) => log_message(format!("Failed to collect device state: {}", e), "ERROR".to_string(), "DeviceManager".to_string()),
+                }
+            }
+        });
+    }
+
+    fn check_link(&self, device_name: &str, worker: &ServoWorker, error: &str) {
+        if !worker.is_closed() {
+            return;
+        }
+
+        // 只移除失效的句柄，避免误删其他调用已经重新打开的连接
+        let removed = match self.lock_workers("reconnect") {
+            Ok(mut workers) => {
+                let stale = workers.get(device_name).is_some_and(|current| current.same_worker(worker));
+                stale && workers.remove(device_name).is_some()
+            }
+            Err(_) => false,
+        };
+        if removed {
+            log_message(format!("Link to {} lost: {}", device_name, error), "ERROR".to_string(), "reconnect".to_string());
+            self.start_reconnect(device_name, error.to_string());
+        }
+    }
+
+    // 在后台按设备的重连策略重新打开端口，同一设备只会有一个重连任务
+    fn start_reconnect(&self, device_name: &str, reason: String) {
+        self.publish_connection(ConnectionEvent {
+            error: Some(reason),
+            ..ConnectionEvent::new(device_name, ConnectionState::Disconnected)
+        });
+
+        let policy = match self.device_config(device_name) {
+            Ok(config) => config.reconnect,
+            Err(_) => return,
+        };
+        if !policy.enabled {
+            log_message(format!("Reconnect disabled for {}", device_name), "INFO".to_string(), "reconnect".to_string());
+            return;
+        }
+        let token = match self.lock_reconnecting("reconnect") {
+            Ok(mut reconnecting) => {
+                if reconnecting.contains_key(device_name) {
+                    return;
+                }
+                let token = self.next_reconnect_id.fetch_add(1, Ordering::SeqCst);
+                reconnecting.insert(device_name.to_string(), token);
+                token
+            }
+            Err(_) => return,
+        };
+
+        let manager = self.clone();
+        let device_name = device_name.to_string();
+        tauri::async_runtime::spawn(async move {
+            manager.reconnect_loop(&device_name, token, &policy).await;
+            if let Ok(mut reconnecting) = manager.reconnecting.lock() {
+                if reconnecting.get(&device_name) == Some(&token) {
+                    reconnecting.remove(&device_name);
+                }
+            }
+        });
+    }
+
+    // 重连任务是否仍然有效，设备被显式连接或断开后任务作废
+    fn is_current_reconnect(&self, device_name: &str, token: u64) -> bool {
+        self.lock_reconnecting("reconnect")
+            .is_ok_and(|reconnecting| reconnecting.get(device_name) == Some(&token))
+    }
+
+    async fn reconnect_loop(&self, device_name: &str, token: u64, policy: &ReconnectPolicy) {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            if !self.is_current_reconnect(device_name, token) {
+                log_message(format!("Reconnect for {} cancelled", device_name), "INFO".to_string(), "reconnect".to_string());
+                return;
+            }
+            if policy.max_attempts.is_some_and(|max| attempt > max) {
+                let error_msg = format!("Giving up on {} after {} reconnect attempts", device_name, attempt - 1);
+                log_message(error_msg.clone(), "ERROR".to_string(), "reconnect".to_string());
+                self.publish_connection(ConnectionEvent {
+                    attempt: attempt - 1,
+                    error: Some(error_msg),
+                    ..ConnectionEvent::new(device_name, ConnectionState::Failed)
+                });
+                return;
+            }
+
+            let delay = policy.delay(attempt);
+            log_message(format!("Reconnecting {} in {:?} (attempt {})", device_name, delay, attempt), "INFO".to_string(), "reconnect".to_string());
+            self.publish_connection(ConnectionEvent {
+                attempt,
+                retry_in_ms: Some(delay.as_millis() as u64),
+                ..ConnectionEvent::new(device_name, ConnectionState::Reconnecting)
+            });
+            tokio::time::sleep(delay).await;
+            if !self.is_current_reconnect(device_name, token) {
+                continue;
+            }
+
+            let config = match self.device_config(device_name) {
+                Ok(config) => config,
+                Err(_) => continue,
+            };
+            // 别名设备重新插入后可能枚举为不同的端口，每次重试都重新解析
+            let port_name = match self.resolve_port(device_name) {
+                Ok(port_name) => port_name,
+                Err(e) => {
+                    log_message(format!("Reconnect attempt {} for {} failed: {}", attempt, device_name, e), "WARN".to_string(), "reconnect".to_string());
+                    continue;
+                }
+            };
+            match ServoWorker::spawn(&port_name, &config, self.emergency_stop.clone()).await {
+                Ok(worker) => {
+                    // 打开端口期间设备可能已被显式断开，此时丢弃新句柄即可关闭端口
+                    if !self.is_current_reconnect(device_name, token) {
+                        return;
+                    }
+                    let worker = match self.lock_workers("reconnect") {
+                        Ok(mut workers) => workers.entry(device_name.to_string()).or_insert(worker).clone(),
+                        Err(_) => continue,
+                    };
+                    log_message(format!("Reconnected {} after {} attempt(s)", device_name, attempt), "INFO".to_string(), "reconnect".to_string());
+                    self.publish_connection(ConnectionEvent {
+                        attempt,
+                        ..ConnectionEvent::new(device_name, ConnectionState::Connected)
+                    });
+
+                    if let Err(e) = self.restore_pose(device_name, &worker, &config).await {
+                        log_message(format!("Failed to restore pose for {}: {}", device_name, e), "WARN".to_string(), "reconnect".to_string());
+                    }
+                    return;
+                }
+                Err(e) => {
+                    log_message(format!("Reconnect attempt {} for {} failed: {}", attempt, device_name, e), "WARN".to_string(), "reconnect".to_string());
+                }
+            }
+        }
+    }
+
+    // 重连后把设备恢复到断开前最后下发的姿态
+    async fn restore_pose(&self, device_name: &str, worker: &ServoWorker, config: &DeviceConfig) -> Result<(), String> {
+        let pose = match self.poses.lock().map_err(|e| format!("Failed to lock poses: {}", e))?.get(device_name) {
+            Some(pose) => pose.clone(),
+            None => return Ok(()),
+        };
+        if self.is_emergency_stopped() {
+            log_message(format!("Emergency stop is active, not restoring pose for {}", device_name), "WARN".to_string(), "reconnect".to_string());
+            return Ok(());
+        }
+
+        let calibrations = self.channel_calibrations(device_name, config)?;
+        let mut channels = Vec::with_capacity(pose.len());
+        for (index, angle) in pose.iter().enumerate().take(config.channels.len()) {
+            let calibration = calibrations.get(index).cloned().unwrap_or_default();
+            channels.push((index as u8, calibration.apply(*angle)?));
+        }
+
+        self.send_channels(device_name, worker, channels).await?;
+        log_message(format!("Restored pose {:?} for {}", pose, device_name), "INFO".to_string(), "reconnect".to_string());
+        Ok(())
+    }
+
+    // 列出串口及 USB 描述信息，probe 为 true 时向候选端口发送身份查询
+    //
+    // 已经打开的端口通过现有的 I/O 线程查询，其余端口在阻塞线程池中并行探测
+    pub async fn discover_devices(&self, probe: bool) -> Result<Vec<DiscoveredPort>, String> {
+        log_message(format!("Discovering devices, probe: {}", probe), "INFO".to_string(), "discover_devices".to_string());
+        let mut ports = discovery::list_ports()?;
+        {
+            let profiles = self.lock_profiles("discover_devices")?;
+            for port in ports.iter_mut() {
+                port.profile = profiles.find_for_port(port).map(|profile| profile.alias.clone());
+            }
+        }
+        if !probe {
+            return Ok(ports);
+        }
+
+        enum Probe {
+            Open(String, ServoWorker),
+            Blocking(tokio::task::JoinHandle<Result<Identification, String>>),
+        }
+
+        let mut probes = Vec::new();
+        for (index, port) in ports.iter().enumerate() {
+            if !port.is_probe_candidate() {
+                continue;
+            }
+            let open_worker = self.lock_workers("discover_devices")?
+                .iter()
+                .find(|(_, worker)| worker.port_name() == port.port_name)
+                .map(|(name, worker)| (name.clone(), worker.clone()));
+            let probe = match open_worker {
+                Some((device_name, worker)) => Probe::Open(device_name, worker),
+                None => {
+                    let port_name = port.port_name.clone();
+                    let config = self.device_config(&port_name)?;
+                    Probe::Blocking(tokio::task::spawn_blocking(move || discovery::probe_port(&port_name, &config)))
+                }
+            };
+            probes.push((index, probe));
+        }
+
+        for (index, probe) in probes {
+            let result = match probe {
+                Probe::Open(device_name, worker) => worker.identify().await.inspect_err(|e| {
+                    self.check_link(&device_name, &worker, e);
+                }),
+                Probe::Blocking(handle) => handle.await
+                    .map_err(|e| format!("Probe task failed: {}", e))
+                    .and_then(|result| result),
+            };
+            ports[index].set_probe_result(result);
+        }
+
+        log_message(format!("Discovered ports: {:?}", ports), "INFO".to_string(), "discover_devices".to_string());
+        Ok(ports)
+    }
+
+    // 两轴命令，只是对 set_servo_channels 的简单封装
+    pub async fn set_servo_position(
+        &self,
+        device_name: String,
+        x: Option<f64>,
+        y: Option<f64>,
+        duration_ms: Option<u64>,
+        profile: Option<String>,
+    ) -> Result<Option<PositionAck>, String> {
+        let mut channels = HashMap::new();
+        if let Some(x) = x {
+            channels.insert("0".to_string(), x);
+        }
+        if let Some(y) = y {
+            channels.insert("1".to_string(), y);
+        }
+        self.set_servo_channels(device_name, channels, duration_ms, profile).await
+    }
+
+    // 设置任意通道的角度，通道可以用名称或序号指定，未指定的通道保持不变
+    //
+    // 返回运动最后一次下发命令的确认结果，运动被取代而没有下发任何命令时返回 None
+    pub async fn set_servo_channels(
+        &self,
+        device_name: String,
+        channels: HashMap<String, f64>,
+        duration_ms: Option<u64>,
+        profile: Option<String>,
+    ) -> Result<Option<PositionAck>, String> {
+        log_message(format!("Setting servo channels for device: {}, channels: {:?}, duration: {:?}, profile: {:?}", device_name, channels, duration_ms, profile), "INFO".to_string(), "set_servo_channels".to_string());
+        let profile = profile.as_deref().map(MotionProfile::parse).transpose()?;
+
+        let ack = self.move_channels(&device_name, channels, duration_ms, profile).await?;
+        log_message(format!("Successfully set servo channels for device: {}, ack: {:?}", device_name, ack), "INFO".to_string(), "set_servo_channels".to_string());
+        Ok(ack)
+    }
+
+    // 规划并执行一段运动，未指定曲线时使用默认曲线
+    async fn move_channels(
+        &self,
+        device_name: &str,
+        channels: HashMap<String, f64>,
+        duration_ms: Option<u64>,
+        profile: Option<MotionProfile>,
+    ) -> Result<Option<PositionAck>, String> {
+        match self.plan_motion(device_name, &channels, duration_ms.map(Duration::from_millis), profile)? {
+            Some(motion) => self.run_motion(device_name, &motion).await,
+            None => Ok(None),
+        }
+    }
+
+    // 规划一段运动但不执行，没有指定任何通道时返回 None
+    fn plan_motion(
+        &self,
+        device_name: &str,
+        channels: &HashMap<String, f64>,
+        duration: Option<Duration>,
+        profile: Option<MotionProfile>,
+    ) -> Result<Option<PlannedMotion>, String> {
+        self.ensure_not_stopped()?;
+
+        let config = self.get_motion_config()?;
+        let profile = profile.unwrap_or(config.default_profile);
+
+        let device_config = self.device_config(device_name)?;
+        let mut targets = Vec::with_capacity(channels.len());
+        for (key, &angle) in channels {
+            let index = device_config.resolve_channel(key)?;
+            targets.push((index, target_angle(key, angle)?));
+        }
+        if targets.is_empty() {
+            return Ok(None);
+        }
+
+        let worker = self.worker(device_name, "set_servo_channels")?;
+
+        let start = self.current_pose(device_name, device_config.channels.len())?;
+        let mut target = start.clone();
+        for &(index, angle) in &targets {
+            target[index] = angle;
+        }
+        let envelope = self.lock_safety("set_servo_channels")?.envelope(device_name);
+        apply_envelope(&envelope, &mut target).inspect_err(|e| {
+            log_message(e.clone(), "WARN".to_string(), "set_servo_channels".to_string());
+        })?;
+        let moving: Vec<usize> = targets.iter().map(|&(index, _)| index).collect();
+        let calibrations = self.channel_calibrations(device_name, &device_config)?;
+        let trajectory = Trajectory::plan(&start, &target, duration, profile, &config)?;
+
+        Ok(Some(PlannedMotion {
+            worker,
+            trajectory,
+            moving,
+            calibrations,
+            envelope,
+            config,
+        }))
+    }
+
+    async fn run_motion(&self, device_name: &str, motion: &PlannedMotion) -> Result<Option<PositionAck>, String> {
+        self.run_trajectory(device_name, motion).await.map_err(|e| {
+            let error_msg = format!("Failed to set servo position: {}", e);
+            log_message(error_msg.clone(), "ERROR".to_string(), "set_servo_channels".to_string());
+            error_msg
+        })
+    }
+
+    // 按固定周期插值执行轨迹，只下发角度发生变化的通道，被新的运动取代时提前返回
+    //
+    // 姿态始终以逻辑角度记录，校准只在下发前应用；每个插值点都会经过安全包络检查
+    async fn run_trajectory(&self, device_name: &str, motion: &PlannedMotion) -> Result<Option<PositionAck>, String> {
+        let PlannedMotion { worker, trajectory, moving, calibrations, envelope, config } = motion;
+        let generation = self.begin_motion(device_name)?;
+        log_message(format!("Motion #{} for {} planned over {:?}", generation, device_name, trajectory.duration()), "INFO".to_string(), "motion".to_string());
+
+        let mut ticker = tokio::time::interval(config.tick());
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        let started = Instant::now();
+        let mut sent: HashMap<usize, u8> = HashMap::new();
+        let mut last_ack = None;
+
+        loop {
+            ticker.tick().await;
+            if self.is_emergency_stopped() {
+                log_message(format!("Motion #{} for {} halted by emergency stop", generation, device_name), "WARN".to_string(), "motion".to_string());
+                return Err("Motion halted by emergency stop".to_string());
+            }
+            if !self.is_current_motion(device_name, generation)? {
+                log_message(format!("Motion #{} for {} superseded", generation, device_name), "INFO".to_string(), "motion".to_string());
+                return Ok(last_ack);
+            }
+
+            let elapsed = started.elapsed();
+            let mut pose = trajectory.sample(elapsed);
+            apply_envelope(envelope, &mut pose)?;
+            let mut changed: Vec<(u8, u8)> = Vec::new();
+            for &index in moving {
+                let calibration = calibrations.get(index).cloned().unwrap_or_default();
+                let angle = calibration.apply(pose[index])?;
+                if sent.get(&index) != Some(&angle) {
+                    changed.push((index as u8, angle));
+                }
+            }
+
+            if !changed.is_empty() {
+                let ack = self.send_channels(device_name, worker, changed.clone()).await?;
+                last_ack = Some(ack);
+                sent.extend(changed.iter().map(|&(index, angle)| (index as usize, angle)));
+            }
+            self.record_pose(device_name, pose)?;
+
+            if elapsed >= trajectory.duration() {
+                return Ok(last_ack);
+            }
+        }
+    }
+
+    // 获取设备当前姿态，至少包含 channel_count 个通道，从未下发过的通道视为中间位置
+    fn current_pose(&self, device_name: &str, channel_count: usize) -> Result<Vec<f64>, String> {
+        let poses = self.poses.lock().map_err(|e| format!("Failed to lock poses: {}", e))?;
+        let mut pose = poses.get(device_name).cloned().unwrap_or_default();
+        if pose.len() < channel_count {
+            pose.resize(channel_count, CENTER_POSITION);
+        }
+        Ok(pose)
+    }
+
+    fn record_pose(&self, device_name: &str, pose: Vec<f64>) -> Result<(), String> {
+        let mut poses = self.poses.lock().map_err(|e| format!("Failed to lock poses: {}", e))?;
+        poses.insert(device_name.to_string(), pose);
+        Ok(())
+    }
+
+    fn begin_motion(&self, device_name: &str) -> Result<u64, String> {
+        let mut generations = self.motion_generations.lock().map_err(|e| format!("Failed to lock motion generations: {}", e))?;
+        let generation = generations.entry(device_name.to_string()).or_insert(0);
+        *generation += 1;
+        Ok(*generation)
+    }
+
+    fn motion_generation(&self, device_name: &str) -> Result<u64, String> {
+        let generations = self.motion_generations.lock().map_err(|e| format!("Failed to lock motion generations: {}", e))?;
+        Ok(generations.get(device_name).copied().unwrap_or(0))
+    }
+
+    fn is_current_motion(&self, device_name: &str, generation: u64) -> Result<bool, String> {
+        let generations = self.motion_generations.lock().map_err(|e| format!("Failed to lock motion generations: {}", e))?;
+        Ok(generations.get(device_name).copied() == Some(generation))
+    }
+
+    // 通过 Ping 检查设备是否在线，不会移动舵机；旧版固件只检查端口是否仍然可用
+    pub async fn ping_device(&self, device_name: String) -> Result<DeviceHealth, String> {
+        log_message(format!("Pinging device: {}", device_name), "INFO".to_string(), "ping_device".to_string());
+
+        let worker = self.worker(&device_name, "ping_device")?;
+        let result = worker.ping().await.inspect_err(|e| {
+            self.check_link(&device_name, &worker, e);
+        });
+
+        let health = match result {
+            Ok(PingResult::Pong(rtt)) => DeviceHealth {
+                online: true,
+                mode: HealthCheckMode::Ping,
+                rtt_ms: Some(rtt.as_secs_f64() * 1000.0),
+                error: None,
             },
-            Err(e) => {
-                let error_msg = format!("Device {} is not responsive: {}", device_name, e);
-                log_message(error_msg.clone(), "WARN".to_string(), "check_device_status".to_string());
-                Ok(false)
+            Ok(PingResult::NoReply) => DeviceHealth {
+                online: false,
+                mode: HealthCheckMode::Ping,
+                rtt_ms: None,
+                error: None,
+            },
+            Ok(PingResult::PortOpen) => DeviceHealth {
+                online: true,
+                mode: HealthCheckMode::Passive,
+                rtt_ms: None,
+                error: None,
             },
+            Err(e) => {
+                log_message(format!("Device {} is not responsive: {}", device_name, e), "WARN".to_string(), "ping_device".to_string());
+                self.update_record(&device_name, |record| record.record_error(&e));
+                // 探测本身已经发出，失败时同样按 Ping 模式报告
+                DeviceHealth {
+                    online: false,
+                    mode: HealthCheckMode::Ping,
+                    rtt_ms: None,
+                    error: Some(e),
+                }
+            }
+        };
+
+        log_message(format!("Device {} health: {:?}", device_name, health), "INFO".to_string(), "ping_device".to_string());
+        Ok(health)
+    }
+
+    // 未连接的设备返回错误，与其他命令一致，不会被当作离线设备
+    pub async fn check_device_status(&self, device_name: String) -> Result<bool, String> {
+        log_message(format!("Checking device status for: {}", device_name), "INFO".to_string(), "check_device_status".to_string());
+        let health = self.ping_device(device_name).await?;
+        Ok(health.online)
+    }
+}
+
+// 检查目标角度并限制在舵机的角度范围内，校准只在下发前应用，因此按逻辑角度限制
+fn target_angle(channel: &str, angle: f64) -> Result<f64, String> {
+    if !angle.is_finite() {
+        return Err(format!("Invalid target angle for channel {}: {}", channel, angle));
+    }
+    let clamped = angle.clamp(SERVO_MIN, SERVO_MAX);
+    if clamped != angle {
+        log_message(format!("Target angle {} for channel {} clamped to {}", angle, channel, clamped), "WARN".to_string(), "set_servo_channels".to_string());
+    }
+    Ok(clamped)
+}
+
+// 检查手势参数，返回 (intensity, speed)，未指定时均为 1
+fn gesture_factors(intensity: Option<f64>, speed: Option<f64>) -> Result<(f64, f64), String> {
+    let intensity = intensity.unwrap_or(1.0);
+    if !(0.0..=MAX_GESTURE_INTENSITY).contains(&intensity) {
+        return Err(format!("Invalid gesture intensity: {}, expected 0 to {}", intensity, MAX_GESTURE_INTENSITY));
+    }
+    let speed = speed.unwrap_or(1.0);
+    if !(MIN_GESTURE_SPEED..=MAX_GESTURE_SPEED).contains(&speed) {
+        return Err(format!("Invalid gesture speed: {}, expected {} to {}", speed, MIN_GESTURE_SPEED, MAX_GESTURE_SPEED));
+    }
+    Ok((intensity, speed))
+}
+
+fn keyframe_duration(keyframe: &Keyframe, speed: f64) -> Result<Duration, String> {
+    Duration::try_from_secs_f64(keyframe.duration_ms as f64 / 1000.0 / speed)
+        .map_err(|e| format!("Invalid keyframe duration {} ms at speed {}: {}", keyframe.duration_ms, speed, e))
+}
+
+// 关键帧的目标角度：起始姿态加上按 intensity 缩放的偏移
+fn keyframe_targets(device_config: &DeviceConfig, origin: &[f64], keyframe: &Keyframe, intensity: f64) -> Result<HashMap<String, f64>, String> {
+    let mut channels = HashMap::new();
+    for (key, offset) in &keyframe.channels {
+        let index = device_config.resolve_channel(key)?;
+        channels.insert(index.to_string(), origin[index] + offset * intensity);
+    }
+    Ok(channels)
+}
+
+// 对姿态的前两个通道(X/Y)应用安全包络
+fn apply_envelope(envelope: &SafetyEnvelope, pose: &mut [f64]) -> Result<(), String> {
+    if let [x, y, ..] = pose {
+        (*x, *y) = envelope.check(*x, *y)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{ErrorKind, Read, Write};
+    use std::thread;
+    use crate::protocol::{Frame, FrameDecoder, FrameKind, PROTOCOL_VERSION};
+    use crate::transport::{LoopbackTransport, Transport};
+
+    // 通过回环对端运行的模拟固件，回复的角度限制在 0..=max_angle 之内
+    //
+    // framed 为 false 时模拟只支持 "x,y\n" 的旧版固件(arduino/initr4.ino)
+    fn spawn_board(device_name: &str, framed: bool, max_angle: u8) {
+        let mut peer = LoopbackTransport::listen(device_name);
+        peer.set_read_timeout(Duration::from_millis(50)).unwrap();
+        thread::spawn(move || {
+            let mut decoder = FrameDecoder::new();
+            let mut line = Vec::new();
+            let mut buf = [0u8; 256];
+            loop {
+                let n = match peer.read(&mut buf) {
+                    Ok(0) => return,
+                    Ok(n) => n,
+                    Err(e) if e.kind() == ErrorKind::TimedOut => continue,
+                    Err(_) => return,
+                };
+
+                if framed {
+                    decoder.push(&buf[..n]);
+                    while let Some(Ok(frame)) = decoder.next_frame() {
+                        let payload = match frame.kind {
+                            FrameKind::Hello => vec![PROTOCOL_VERSION],
+                            FrameKind::SetPosition => frame.payload.chunks_exact(2)
+                                .flat_map(|pair| [pair[0], pair[1].min(max_angle)])
+                                .collect(),
+                            _ => Vec::new(),
+                        };
+                        let reply = Frame::new(PROTOCOL_VERSION, frame.seq, FrameKind::Ack, payload);
+                        peer.write_all(&reply.encode().unwrap()).unwrap();
+                    }
+                    continue;
+                }
+
+                for &byte in &buf[..n] {
+                    if byte != b'\n' {
+                        line.push(byte);
+                        continue;
+                    }
+                    let text = String::from_utf8_lossy(&line).into_owned();
+                    line.clear();
+                    if let Some((x, y)) = text.trim().split_once(',') {
+                        let clamp = |value: &str| value.trim().parse::<u8>().unwrap_or(0).min(max_angle);
+                        let reply = format!("Position set to: {},{}\r\n", clamp(x), clamp(y));
+                        peer.write_all(reply.as_bytes()).unwrap();
+                    }
+                }
+            }
+        });
+    }
+
+    async fn connect(device_name: &str, framed: bool, max_angle: u8) -> DeviceManager {
+        spawn_board(device_name, framed, max_angle);
+        let manager = DeviceManager::new();
+        manager.connect_device(device_name.to_string(), None).await.unwrap();
+        manager
+    }
+
+    #[tokio::test]
+    async fn framed_board_acknowledges_positions() {
+        let manager = connect("loopback://framed-ack", true, 180).await;
+
+        let ack = manager.set_servo_position("loopback://framed-ack".to_string(), Some(120.0), None, None, None).await
+            .unwrap()
+            .unwrap();
+        assert_eq!(ack.status, AckStatus::Acked);
+        assert!(ack.reported.contains(&(0, 120)));
+
+        assert!(manager.check_device_status("loopback://framed-ack".to_string()).await.unwrap());
+        let health = manager.ping_device("loopback://framed-ack".to_string()).await.unwrap();
+        assert_eq!(health.mode, HealthCheckMode::Ping);
+        assert!(health.rtt_ms.is_some());
+    }
+
+    #[tokio::test]
+    async fn failed_ping_is_reported_with_its_error() {
+        // 只回复握手，收到 Ping 后断开连接
+        let mut peer = LoopbackTransport::listen("loopback://framed-drop");
+        thread::spawn(move || {
+            let mut decoder = FrameDecoder::new();
+            let mut buf = [0u8; 256];
+            while let Ok(n) = peer.read(&mut buf) {
+                decoder.push(&buf[..n]);
+                while let Some(Ok(frame)) = decoder.next_frame() {
+                    if frame.kind != FrameKind::Hello {
+                        return;
+                    }
+                    let reply = Frame::new(PROTOCOL_VERSION, frame.seq, FrameKind::Ack, vec![PROTOCOL_VERSION]);
+                    peer.write_all(&reply.encode().unwrap()).unwrap();
+                }
+            }
+        });
+        let manager = DeviceManager::new();
+        manager.connect_device("loopback://framed-drop".to_string(), None).await.unwrap();
+
+        let health = manager.ping_device("loopback://framed-drop".to_string()).await.unwrap();
+        assert!(!health.online);
+        assert_eq!(health.mode, HealthCheckMode::Ping);
+        assert!(health.error.is_some());
+    }
+
+    #[tokio::test]
+    async fn framed_board_reports_clamped_angles() {
+        let manager = connect("loopback://framed-clamp", true, 100).await;
+
+        let ack = manager.set_servo_position("loopback://framed-clamp".to_string(), Some(130.0), None, None, None).await
+            .unwrap()
+            .unwrap();
+        assert_eq!(ack.status, AckStatus::Clamped);
+        assert_eq!(ack.commanded, vec![(0, 130)]);
+        assert_eq!(ack.reported, vec![(0, 100)]);
+        assert!(manager.get_link_stats("loopback://framed-clamp".to_string()).unwrap().mismatches >= 1);
+    }
+
+    #[tokio::test]
+    async fn target_angles_are_checked_before_planning() {
+        let manager = connect("loopback://framed-range", true, 180).await;
+
+        let ack = manager.set_servo_position("loopback://framed-range".to_string(), Some(1e308), None, Some(0), None).await
+            .unwrap()
+            .unwrap();
+        assert_eq!(ack.commanded, vec![(0, 180)]);
+        assert_eq!(manager.current_pose("loopback://framed-range", 2).unwrap()[0], 180.0);
+
+        for invalid in [f64::NAN, f64::INFINITY] {
+            let result = manager.set_servo_position("loopback://framed-range".to_string(), Some(invalid), None, None, None).await;
+            assert!(result.unwrap_err().contains("Invalid target angle"));
         }
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn group_gesture_leaves_members_moved_during_a_hold() {
+        let manager = connect("loopback://group-a", true, 180).await;
+        spawn_board("loopback://group-b", true, 180);
+        manager.connect_device("loopback://group-b".to_string(), None).await.unwrap();
+        manager.set_group(DeviceGroup {
+            name: "desk".to_string(),
+            members: vec!["loopback://group-a".to_string(), "loopback://group-b".to_string()],
+        }).unwrap();
+
+        // tilt: 400 ms 的运动之后停顿 600 ms，在停顿期间移动成员 a
+        let player = manager.clone();
+        let gesture = tokio::spawn(async move {
+            player.play_group_gesture("desk".to_string(), "tilt".to_string(), None, None).await
+        });
+        tokio::time::sleep(Duration::from_millis(700)).await;
+        manager.set_servo_position("loopback://group-a".to_string(), Some(30.0), None, Some(0), None).await
+            .unwrap();
+
+        let results = gesture.await.unwrap().unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|result| result.success));
+        assert_eq!(manager.current_pose("loopback://group-a", 2).unwrap()[0], 30.0);
+        assert_eq!(manager.current_pose("loopback://group-b", 2).unwrap()[0], 90.0);
+    }
+
+    #[tokio::test]
+    async fn wide_position_commands_are_split_into_frames() {
+        let device_name = "loopback://framed-wide";
+        spawn_board(device_name, true, 180);
+        let manager = DeviceManager::new();
+        let config = DeviceConfig {
+            channels: (0..40).map(|index| format!("c{}", index)).collect(),
+            ..DeviceConfig::default()
+        };
+        manager.connect_device(device_name.to_string(), Some(config)).await.unwrap();
+
+        let channels = (0..40).map(|index| (format!("c{}", index), 100.0)).collect();
+        let ack = manager.set_servo_channels(device_name.to_string(), channels, None, None).await
+            .unwrap()
+            .unwrap();
+        assert_eq!(ack.status, AckStatus::Acked);
+        assert_eq!(ack.reported.len(), 40);
+    }
+
+    #[test]
+    fn device_config_rejects_bad_channel_lists() {
+        let config = |channels: Vec<&str>| DeviceConfig {
+            channels: channels.into_iter().map(str::to_string).collect(),
+            ..DeviceConfig::default()
+        };
+        assert!(config(vec!["pan", "tilt"]).validate().is_ok());
+        assert!(config(vec![]).validate().is_err());
+        assert!(config(vec!["pan", "Pan"]).validate().is_err());
+        assert!(config(vec!["pan", " "]).validate().is_err());
+        assert!(config(vec!["c"; MAX_CHANNELS + 1]).validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn legacy_board_falls_back_to_text_protocol() {
+        let manager = connect("loopback://legacy", false, 180).await;
+
+        let ack = manager.set_servo_position("loopback://legacy".to_string(), Some(60.0), Some(100.0), None, None).await
+            .unwrap()
+            .unwrap();
+        assert_eq!(ack.status, AckStatus::Acked);
+        assert_eq!(ack.reported, vec![(0, 60), (1, 100)]);
+
+        assert!(manager.check_device_status("loopback://legacy".to_string()).await.unwrap());
+        let health = manager.ping_device("loopback://legacy".to_string()).await.unwrap();
+        assert_eq!(health.mode, HealthCheckMode::Passive);
+    }
+
+    #[test]
+    fn gesture_factors_are_bounded() {
+        assert_eq!(gesture_factors(None, None), Ok((1.0, 1.0)));
+        assert!(gesture_factors(Some(f64::NAN), None).is_err());
+        assert!(gesture_factors(Some(1e300), None).is_err());
+        assert!(gesture_factors(None, Some(0.0)).is_err());
+        assert!(gesture_factors(None, Some(1e-300)).is_err());
+        assert!(gesture_factors(None, Some(f64::INFINITY)).is_err());
+
+        let keyframe = Keyframe { channels: HashMap::new(), duration_ms: 500, profile: None };
+        assert_eq!(keyframe_duration(&keyframe, 2.0), Ok(Duration::from_millis(250)));
+    }
+
+    #[tokio::test]
+    async fn commands_require_a_connected_device() {
+        let manager = DeviceManager::new();
+        let result = manager.set_servo_position("loopback://missing".to_string(), Some(90.0), None, None, None).await;
+        assert!(result.unwrap_err().contains("not connected"));
+        let result = manager.check_device_status("loopback://missing".to_string()).await;
+        assert!(result.unwrap_err().contains("not connected"));
+    }
+}
@@ -0,0 +1,104 @@
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+use serde::Serialize;
+use crate::device_manager::LinkStats;
+use crate::reconnect::ConnectionState;
+use crate::servo_controller::PositionAck;
+
+// 设备当前状态的快照，通道均以名称索引
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceState {
+    pub device_name: String,
+    pub connection: ConnectionState,
+    // 各通道最近一次下发的逻辑角度
+    pub commanded: HashMap<String, f64>,
+    // 各通道最近一次由设备确认的实际角度
+    pub acknowledged: HashMap<String, u8>,
+    // 下发的命令数，包括失败的命令
+    pub commands: u64,
+    pub errors: u64,
+    pub last_error: Option<String>,
+    // 命令从下发到收到确认的平均耗时(毫秒)
+    pub average_latency_ms: Option<f64>,
+    // 当前连接已持续的时间(毫秒)，未连接时为空
+    pub uptime_ms: Option<u64>,
+    pub link: LinkStats,
+}
+
+// DeviceManager 内部为每个设备记录的运行数据
+pub struct DeviceRecord {
+    connection: ConnectionState,
+    connected_since: Option<Instant>,
+    // 按通道序号索引的确认角度
+    acknowledged: BTreeMap<u8, u8>,
+    commands: u64,
+    errors: u64,
+    last_error: Option<String>,
+    latency_total: Duration,
+    latency_samples: u32,
+}
+
+impl Default for DeviceRecord {
+    fn default() -> Self {
+        DeviceRecord {
+            connection: ConnectionState::Disconnected,
+            connected_since: None,
+            acknowledged: BTreeMap::new(),
+            commands: 0,
+            errors: 0,
+            last_error: None,
+            latency_total: Duration::ZERO,
+            latency_samples: 0,
+        }
+    }
+}
+
+impl DeviceRecord {
+    pub fn set_connection(&mut self, state: ConnectionState) {
+        if state != ConnectionState::Connected {
+            self.connected_since = None;
+        } else if self.connection != ConnectionState::Connected {
+            self.connected_since = Some(Instant::now());
+        }
+        self.connection = state;
+    }
+
+    // 记录一次位置命令的结果，超时未确认的命令不计入延迟
+    pub fn record_command(&mut self, result: &Result<PositionAck, String>, latency: Duration) {
+        self.commands += 1;
+        match result {
+            Ok(ack) if !ack.reported.is_empty() => {
+                self.acknowledged.extend(ack.reported.iter().copied());
+                self.latency_total += latency;
+                self.latency_samples += 1;
+            }
+            Ok(_) => {}
+            Err(e) => self.record_error(e),
+        }
+    }
+
+    pub fn record_error(&mut self, error: &str) {
+        self.errors += 1;
+        self.last_error = Some(error.to_string());
+    }
+
+    // 生成状态快照，channels 为通道名称，pose 为按通道序号排列的逻辑角度
+    pub fn snapshot(&self, device_name: &str, channels: &[String], pose: &[f64], link: LinkStats) -> DeviceState {
+        let channel_name = |index: usize| channels.get(index).cloned().unwrap_or_else(|| index.to_string());
+
+        DeviceState {
+            device_name: device_name.to_string(),
+            connection: self.connection,
+            commanded: pose.iter().enumerate().map(|(index, &angle)| (channel_name(index), angle)).collect(),
+            acknowledged: self.acknowledged.iter().map(|(&index, &angle)| (channel_name(index as usize), angle)).collect(),
+            commands: self.commands,
+            errors: self.errors,
+            last_error: self.last_error.clone(),
+            average_latency_ms: (self.latency_samples > 0)
+                .then(|| self.latency_total.as_secs_f64() * 1000.0 / self.latency_samples as f64),
+            uptime_ms: self.connected_since.map(|since| since.elapsed().as_millis() as u64),
+            link,
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use serde::Serialize;
+use serialport::SerialPortType;
+use crate::commands::log_message;
+use crate::device_manager::DeviceConfig;
+use crate::protocol::DeviceIdentity;
+use crate::servo_controller::{Identification, ServoController};
+use crate::virtual_device::DEFAULT_VIRTUAL_DEVICE;
+
+// 定义模块名称常量
+const MODEL_NAME: &str = "discovery";
+
+// 串口的物理类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PortKind {
+    Usb,
+    Pci,
+    Bluetooth,
+    // 内置的虚拟设备
+    Virtual,
+    Unknown,
+}
+
+// 探测到的设备所用的串口协议
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PortProtocol {
+    Framed,
+    Legacy,
+}
+
+// 发现的串口及其元数据
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoveredPort {
+    pub port_name: String,
+    pub kind: PortKind,
+    pub vid: Option<u16>,
+    pub pid: Option<u16>,
+    pub serial_number: Option<String>,
+    pub manufacturer: Option<String>,
+    pub product: Option<String>,
+    // 是否为 Desky 兼容设备，未探测的端口为 None，无法打开或不按 Desky 协议回复的端口为 false
+    pub compatible: Option<bool>,
+    // 兼容设备所用的协议
+    pub protocol: Option<PortProtocol>,
+    // 设备对身份查询的回复，旧版固件的版本和通道数未知，为 None
+    pub identity: Option<DeviceIdentity>,
+    // 探测失败的原因
+    pub probe_error: Option<String>,
+    // 匹配该端口的设备档案别名
+    pub profile: Option<String>,
+}
+
+impl DiscoveredPort {
+    fn new(port_name: String, kind: PortKind) -> Self {
+        DiscoveredPort {
+            port_name,
+            kind,
+            vid: None,
+            pid: None,
+            serial_number: None,
+            manufacturer: None,
+            product: None,
+            compatible: None,
+            protocol: None,
+            identity: None,
+            probe_error: None,
+            profile: None,
+        }
+    }
+
+    // 只探测 USB 串口和虚拟设备，板载串口(如 /dev/ttyS0)通常不是设备，打开后还可能长时间阻塞
+    pub fn is_probe_candidate(&self) -> bool {
+        matches!(self.kind, PortKind::Usb | PortKind::Virtual)
+    }
+
+    pub fn set_probe_result(&mut self, result: Result<Identification, String>) {
+        match result {
+            Ok(Identification::Legacy) => {
+                self.compatible = Some(true);
+                self.protocol = Some(PortProtocol::Legacy);
+            }
+            Ok(Identification::Framed(identity)) => {
+                self.compatible = Some(true);
+                self.protocol = Some(PortProtocol::Framed);
+                self.identity = identity;
+            }
+            Err(e) => {
+                self.compatible = Some(false);
+                self.probe_error = Some(e);
+            }
+        }
+    }
+}
+
+// 列出系统中的串口及 USB 描述信息，内置的虚拟设备排在最后
+pub fn list_ports() -> Result<Vec<DiscoveredPort>, String> {
+    let ports = serialport::available_ports().map_err(|e| {
+        let error_msg = format!("Error listing serial ports: {}", e);
+        log_message(error_msg.clone(), "ERROR".to_string(), MODEL_NAME.to_string());
+        error_msg
+    })?;
+
+    let mut discovered: Vec<DiscoveredPort> = ports.into_iter().map(|port| {
+        let mut discovered = DiscoveredPort::new(port.port_name, PortKind::Unknown);
+        match port.port_type {
+            SerialPortType::UsbPort(info) => {
+                discovered.kind = PortKind::Usb;
+                discovered.vid = Some(info.vid);
+                discovered.pid = Some(info.pid);
+                discovered.serial_number = info.serial_number;
+                discovered.manufacturer = info.manufacturer;
+                discovered.product = info.product;
+            }
+            SerialPortType::PciPort => discovered.kind = PortKind::Pci,
+            SerialPortType::BluetoothPort => discovered.kind = PortKind::Bluetooth,
+            SerialPortType::Unknown => {}
+        }
+        discovered
+    }).collect();
+    discovered.push(DiscoveredPort::new(DEFAULT_VIRTUAL_DEVICE.to_string(), PortKind::Virtual));
+    Ok(discovered)
+}
+
+// 临时打开端口并发送身份查询，属于阻塞操作
+//
+// 只有支持帧协议的固件能回复身份查询，没有回复握手的端口按旧版文本协议的固件报告
+pub fn probe_port(port_name: &str, config: &DeviceConfig) -> Result<Identification, String> {
+    log_message(format!("Probing port {}", port_name), "INFO".to_string(), MODEL_NAME.to_string());
+    let mut controller = ServoController::new(port_name, config)
+        .map_err(|e| format!("Failed to open {}: {}", port_name, e))?;
+    controller.identify()
+        .map_err(|e| format!("Identify failed on {}: {}", port_name, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_boards_are_compatible_without_identity() {
+        let mut port = DiscoveredPort::new("COM3".to_string(), PortKind::Usb);
+        port.set_probe_result(Ok(Identification::Legacy));
+        assert_eq!(port.compatible, Some(true));
+        assert_eq!(port.protocol, Some(PortProtocol::Legacy));
+        assert_eq!(port.identity, None);
+    }
+
+    #[test]
+    fn failed_probes_are_incompatible() {
+        let mut port = DiscoveredPort::new("COM3".to_string(), PortKind::Usb);
+        port.set_probe_result(Err("Failed to open COM3".to_string()));
+        assert_eq!(port.compatible, Some(false));
+        assert_eq!(port.protocol, None);
+        assert!(port.probe_error.is_some());
+    }
+
+    #[test]
+    fn virtual_device_is_probed_over_the_framed_protocol() {
+        let identification = probe_port(DEFAULT_VIRTUAL_DEVICE, &DeviceConfig::default()).unwrap();
+        let mut port = DiscoveredPort::new(DEFAULT_VIRTUAL_DEVICE.to_string(), PortKind::Virtual);
+        port.set_probe_result(Ok(identification));
+        assert_eq!(port.compatible, Some(true));
+        assert_eq!(port.protocol, Some(PortProtocol::Framed));
+        assert!(port.identity.is_some());
+    }
+}
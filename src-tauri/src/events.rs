@@ -0,0 +1,45 @@
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+use crate::commands::log_message;
+
+// 定义模块名称常量
+const MODEL_NAME: &str = "events";
+
+// 串口出现或消失时发送的事件
+pub const DEVICE_ATTACHED: &str = "device-attached";
+pub const DEVICE_DETACHED: &str = "device-detached";
+// 设备连接状态变化时发送的事件
+pub const DEVICE_CONNECTION: &str = "device-connection";
+// 周期性发送的所有设备状态
+pub const DEVICE_STATE: &str = "device-state";
+// 虚拟设备运动时发送的模拟角度
+pub const VIRTUAL_POSE: &str = "virtual-device-pose";
+// 流式 HTTP 请求的响应片段、SSE 事件以及结束和出错通知
+pub const HTTP_STREAM_CHUNK: &str = "http-stream-chunk";
+pub const HTTP_STREAM_EVENT: &str = "http-stream-event";
+pub const HTTP_STREAM_END: &str = "http-stream-end";
+pub const HTTP_STREAM_ERROR: &str = "http-stream-error";
+
+// 应用启动后保存的句柄，后台任务通过它向前端发送事件
+static APP_HANDLE: OnceCell<AppHandle> = OnceCell::new();
+
+pub fn init(handle: AppHandle) {
+    if APP_HANDLE.set(handle).is_err() {
+        log_message("Event emitter already initialized".to_string(), "WARN".to_string(), MODEL_NAME.to_string());
+    }
+}
+
+// 向所有窗口发送事件，应用尚未启动完成时只记录日志
+pub fn emit<S: Serialize + Clone>(event: &str, payload: S) {
+    match APP_HANDLE.get() {
+        Some(handle) => {
+            if let Err(e) = handle.emit_all(event, payload) {
+                log_message(format!("Failed to emit {}: {}", event, e), "ERROR".to_string(), MODEL_NAME.to_string());
+            }
+        }
+        None => {
+            log_message(format!("Event {} dropped, app is not ready", event), "WARN".to_string(), MODEL_NAME.to_string());
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::commands::log_message;
+use crate::motion_planner::MotionProfile;
+
+// 定义模块名称常量
+const MODEL_NAME: &str = "gesture";
+// 自定义手势在应用数据目录中的子目录，每个 JSON 文件定义一个手势
+pub const GESTURE_DIR: &str = "gestures";
+
+// 内置手势使用通道序号，这样重命名过通道的设备也能直接使用
+const PAN: &str = "0";
+const TILT: &str = "1";
+
+// 单个关键帧的最长时间(毫秒)
+pub const MAX_KEYFRAME_DURATION_MS: u64 = 60_000;
+// 播放手势时 intensity 和 speed 的取值范围
+pub const MAX_GESTURE_INTENSITY: f64 = 5.0;
+pub const MIN_GESTURE_SPEED: f64 = 0.1;
+pub const MAX_GESTURE_SPEED: f64 = 10.0;
+
+// 手势中的一个关键帧
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Keyframe {
+    // 各通道相对手势起始姿态的偏移(度)，通道可以用名称或序号指定；为空时表示停顿
+    #[serde(default)]
+    pub channels: HashMap<String, f64>,
+    // 到达该关键帧所用的时间(毫秒)
+    pub duration_ms: u64,
+    // 该段使用的运动曲线，未指定时使用默认曲线
+    #[serde(default)]
+    pub profile: Option<MotionProfile>,
+}
+
+impl Keyframe {
+    fn new(channels: &[(&str, f64)], duration_ms: u64) -> Self {
+        Keyframe {
+            channels: channels.iter().map(|&(channel, offset)| (channel.to_string(), offset)).collect(),
+            duration_ms,
+            profile: None,
+        }
+    }
+
+    fn hold(duration_ms: u64) -> Self {
+        Keyframe::new(&[], duration_ms)
+    }
+}
+
+// 一个命名的关键帧序列
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Gesture {
+    // 文件中未指定名称时使用文件名
+    #[serde(default)]
+    pub name: String,
+    pub keyframes: Vec<Keyframe>,
+}
+
+impl Gesture {
+    fn new(name: &str, keyframes: Vec<Keyframe>) -> Self {
+        Gesture {
+            name: name.to_string(),
+            keyframes,
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Gesture name must not be empty".to_string());
+        }
+        if self.keyframes.is_empty() {
+            return Err(format!("Gesture {} has no keyframes", self.name));
+        }
+        if self.keyframes.iter().flat_map(|keyframe| keyframe.channels.values()).any(|v| !v.is_finite()) {
+            return Err(format!("Gesture {} contains invalid offsets", self.name));
+        }
+        if let Some(keyframe) = self.keyframes.iter().find(|keyframe| keyframe.duration_ms > MAX_KEYFRAME_DURATION_MS) {
+            return Err(format!(
+                "Gesture {} has a keyframe lasting {} ms, the maximum is {} ms",
+                self.name, keyframe.duration_ms, MAX_KEYFRAME_DURATION_MS
+            ));
+        }
+        Ok(())
+    }
+}
+
+// 内置的手势
+fn builtin_gestures() -> Vec<Gesture> {
+    vec![
+        Gesture::new("nod", vec![
+            Keyframe::new(&[(TILT, -15.0)], 200),
+            Keyframe::new(&[(TILT, 12.0)], 300),
+            Keyframe::new(&[(TILT, -8.0)], 250),
+            Keyframe::new(&[(TILT, 0.0)], 200),
+        ]),
+        Gesture::new("shake", vec![
+            Keyframe::new(&[(PAN, -20.0)], 200),
+            Keyframe::new(&[(PAN, 20.0)], 350),
+            Keyframe::new(&[(PAN, -15.0)], 300),
+            Keyframe::new(&[(PAN, 0.0)], 200),
+        ]),
+        Gesture::new("tilt", vec![
+            Keyframe::new(&[(PAN, 8.0), (TILT, 15.0)], 400),
+            Keyframe::hold(600),
+            Keyframe::new(&[(PAN, 0.0), (TILT, 0.0)], 400),
+        ]),
+        Gesture::new("look-around", vec![
+            Keyframe::new(&[(PAN, -35.0), (TILT, 10.0)], 600),
+            Keyframe::hold(300),
+            Keyframe::new(&[(PAN, 35.0)], 1000),
+            Keyframe::hold(300),
+            Keyframe::new(&[(PAN, 0.0), (TILT, 0.0)], 600),
+        ]),
+    ]
+}
+
+// 内置手势加上应用数据目录中加载的自定义手势，同名时自定义手势优先
+pub struct GestureLibrary {
+    gestures: HashMap<String, Gesture>,
+}
+
+impl Default for GestureLibrary {
+    fn default() -> Self {
+        GestureLibrary {
+            gestures: builtin_gestures().into_iter()
+                .map(|gesture| (gesture.name.clone(), gesture))
+                .collect(),
+        }
+    }
+}
+
+impl GestureLibrary {
+    // 加载目录中的所有 *.json 手势文件，单个文件无效时跳过并记录警告
+    pub fn load(dir: PathBuf) -> Result<Self, String> {
+        let mut library = GestureLibrary::default();
+        if !dir.exists() {
+            return Ok(library);
+        }
+
+        let entries = std::fs::read_dir(&dir)
+            .map_err(|e| format!("Failed to read gesture directory {}: {}", dir.display(), e))?;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+
+            match load_gesture(&path) {
+                Ok(gesture) => {
+                    log_message(format!("Loaded gesture {} from {}", gesture.name, path.display()), "INFO".to_string(), MODEL_NAME.to_string());
+                    library.gestures.insert(gesture.name.clone(), gesture);
+                }
+                Err(e) => {
+                    log_message(format!("Skipping gesture file {}: {}", path.display(), e), "WARN".to_string(), MODEL_NAME.to_string());
+                }
+            }
+        }
+
+        Ok(library)
+    }
+
+    pub fn get(&self, name: &str) -> Option<Gesture> {
+        self.gestures.get(name).cloned()
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.gestures.keys().cloned().collect();
+        names.sort();
+        names
+    }
+}
+
+fn load_gesture(path: &Path) -> Result<Gesture, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let mut gesture: Gesture = serde_json::from_str(&content).map_err(|e| format!("Failed to parse gesture: {}", e))?;
+    if gesture.name.trim().is_empty() {
+        gesture.name = path.file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default()
+            .to_string();
+    }
+    gesture.validate()?;
+    Ok(gesture)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_gestures_are_valid() {
+        for gesture in builtin_gestures() {
+            assert_eq!(gesture.validate(), Ok(()), "{}", gesture.name);
+        }
+    }
+
+    #[test]
+    fn overlong_keyframes_are_rejected() {
+        let gesture = Gesture::new("slow", vec![Keyframe::new(&[(PAN, 10.0)], u64::MAX)]);
+        assert!(gesture.validate().unwrap_err().contains("maximum"));
+        let gesture = Gesture::new("slow", vec![Keyframe::hold(MAX_KEYFRAME_DURATION_MS)]);
+        assert_eq!(gesture.validate(), Ok(()));
+    }
+}
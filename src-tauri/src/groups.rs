@@ -0,0 +1,112 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use crate::app_data::JsonFile;
+use crate::commands::log_message;
+use crate::servo_controller::PositionAck;
+
+// 定义模块名称常量
+const MODEL_NAME: &str = "groups";
+// 设备组在应用数据目录中的文件名
+pub const GROUP_FILE: &str = "groups.json";
+
+// 一组同步运动的设备，成员可以是设备名称或档案别名
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceGroup {
+    pub name: String,
+    pub members: Vec<String>,
+}
+
+impl DeviceGroup {
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Group name must not be empty".to_string());
+        }
+        if self.members.is_empty() {
+            return Err(format!("Group {} needs at least one member", self.name));
+        }
+        for (index, member) in self.members.iter().enumerate() {
+            if self.members[..index].contains(member) {
+                return Err(format!("Device {} appears more than once in group {}", member, self.name));
+            }
+        }
+        Ok(())
+    }
+}
+
+// 组命令中单个成员的执行结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemberResult {
+    pub device_name: String,
+    pub success: bool,
+    // 成员最后一次下发命令的确认结果
+    pub ack: Option<PositionAck>,
+    pub error: Option<String>,
+}
+
+impl MemberResult {
+    pub fn new(device_name: &str, result: Result<Option<PositionAck>, String>) -> Self {
+        let (ack, error) = match result {
+            Ok(ack) => (ack, None),
+            Err(e) => (None, Some(e)),
+        };
+        MemberResult {
+            device_name: device_name.to_string(),
+            success: error.is_none(),
+            ack,
+            error,
+        }
+    }
+}
+
+// 所有设备组，按组名索引并持久化到应用数据目录
+#[derive(Default)]
+pub struct GroupStore {
+    groups: JsonFile<BTreeMap<String, DeviceGroup>>,
+}
+
+impl GroupStore {
+    pub fn load(path: PathBuf) -> Result<Self, String> {
+        let groups: JsonFile<BTreeMap<String, DeviceGroup>> = JsonFile::load(path.clone())?;
+        for group in groups.data().values() {
+            group.validate()
+                .map_err(|e| format!("Invalid device group {} in {}: {}", group.name, path.display(), e))?;
+        }
+
+        log_message(
+            format!("Loaded {} device group(s) from {}", groups.data().len(), path.display()),
+            "INFO".to_string(),
+            MODEL_NAME.to_string(),
+        );
+
+        Ok(GroupStore { groups })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&DeviceGroup> {
+        self.groups.data().get(name)
+    }
+
+    pub fn list(&self) -> Vec<DeviceGroup> {
+        self.groups.data().values().cloned().collect()
+    }
+
+    pub fn set(&mut self, group: DeviceGroup) -> Result<(), String> {
+        group.validate()?;
+        self.groups.update("device groups", |groups| {
+            groups.insert(group.name.clone(), group);
+            Ok(())
+        })
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<bool, String> {
+        if !self.groups.data().contains_key(name) {
+            return Ok(false);
+        }
+        self.groups.update("device groups", |groups| {
+            groups.remove(name);
+            Ok(true)
+        })
+    }
+}
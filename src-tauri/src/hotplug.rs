@@ -0,0 +1,72 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use serde::Serialize;
+use crate::commands::log_message;
+use crate::device_manager::DeviceManager;
+use crate::discovery::{self, DiscoveredPort};
+use crate::events::{self, DEVICE_ATTACHED, DEVICE_DETACHED};
+
+// 定义模块名称常量
+const MODEL_NAME: &str = "hotplug";
+// 轮询串口列表的间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// device-detached 事件的负载
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DetachedEvent {
+    port_name: String,
+    // 拔出前设备是否处于打开状态
+    was_connected: bool,
+}
+
+// 启动后台任务，定期比较串口列表并发送插拔事件
+//
+// 启动时已经存在的端口作为初始状态，不会产生 device-attached 事件
+pub fn spawn_watcher(device_manager: Arc<DeviceManager>) {
+    tauri::async_runtime::spawn(async move {
+        log_message("Hot-plug watcher started".to_string(), "INFO".to_string(), MODEL_NAME.to_string());
+        let mut known: Option<HashSet<String>> = None;
+        let mut ticker = tokio::time::interval(POLL_INTERVAL);
+
+        loop {
+            ticker.tick().await;
+            let ports = match tokio::task::spawn_blocking(discovery::list_ports).await {
+                Ok(Ok(ports)) => ports,
+                Ok(Err(_)) => continue,
+                Err(e) => {
+                    log_message(format!("Port listing task failed: {}", e), "ERROR".to_string(), MODEL_NAME.to_string());
+                    continue;
+                }
+            };
+
+            let current: HashMap<String, DiscoveredPort> = ports.into_iter()
+                .map(|port| (port.port_name.clone(), port))
+                .collect();
+            let previous = match known.replace(current.keys().cloned().collect()) {
+                Some(previous) => previous,
+                None => continue,
+            };
+
+            for (port_name, port) in &current {
+                if !previous.contains(port_name) {
+                    log_message(format!("Serial port attached: {}", port_name), "INFO".to_string(), MODEL_NAME.to_string());
+                    events::emit(DEVICE_ATTACHED, port.clone());
+                }
+            }
+
+            for port_name in previous.iter().filter(|name| !current.contains_key(*name)) {
+                let was_connected = device_manager.mark_detached(port_name).unwrap_or_else(|e| {
+                    log_message(e, "ERROR".to_string(), MODEL_NAME.to_string());
+                    false
+                });
+                log_message(format!("Serial port detached: {} (was connected: {})", port_name, was_connected), "WARN".to_string(), MODEL_NAME.to_string());
+                events::emit(DEVICE_DETACHED, DetachedEvent {
+                    port_name: port_name.clone(),
+                    was_connected,
+                });
+            }
+        }
+    });
+}
@@ -1,16 +1,145 @@
 // 引入必要的外部依赖
-use reqwest::{Client, Response};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Client, Method, Response};
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
 
 // 引入本地日志模块
 use crate::commands::log_message;
+use crate::multipart::MultipartForm;
+use crate::request_policy::{parse_retry_after, RequestPolicy, RequestPolicyOverride};
 
 // 定义模块名称常量
 const MODEL_NAME: &str = "HttpClient";
 
+// 期望的响应体格式，默认根据 Content-Type 判断
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResponseType {
+    #[default]
+    Auto,
+    Text,
+    Json,
+    Bytes,
+}
+
+// 响应体，前端按 type 字段区分
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", content = "data", rename_all = "lowercase")]
+pub enum ResponseBody {
+    Text(String),
+    Json(serde_json::Value),
+    // 二进制内容(例如 TTS 返回的音频)原样返回，不做 UTF-8 解码
+    Bytes(Vec<u8>),
+}
+
+impl ResponseBody {
+    // 转换为文本，供只需要字符串的旧接口使用
+    pub fn into_text(self) -> String {
+        match self {
+            ResponseBody::Text(text) => text,
+            ResponseBody::Json(value) => value.to_string(),
+            ResponseBody::Bytes(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+        }
+    }
+}
+
+// 代理请求的完整响应
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyResponse {
+    pub status: u16,
+    // 状态码是否为 2xx
+    pub ok: bool,
+    // 响应头，名称为小写，同名的多个值以逗号连接
+    pub headers: HashMap<String, String>,
+    // 跟随重定向后的最终地址
+    pub url: String,
+    // 从发送请求到读取完响应体的耗时(毫秒)
+    pub elapsed_ms: u64,
+    pub body: ResponseBody,
+}
+
+impl ProxyResponse {
+    // 读取完整的响应体并按 response_type 转换，读取过程同样受策略的读取超时和总超时限制
+    pub async fn read(mut response: Response, started: Instant, response_type: ResponseType, policy: &RequestPolicy) -> Result<Self, String> {
+        let status = response.status();
+        let url = response.url().to_string();
+        let mut headers: HashMap<String, String> = HashMap::new();
+        for (name, value) in response.headers() {
+            let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+            headers.entry(name.as_str().to_string())
+                .and_modify(|existing| {
+                    existing.push_str(", ");
+                    existing.push_str(&value);
+                })
+                .or_insert(value);
+        }
+        let content_type = headers.get("content-type").cloned().unwrap_or_default();
+
+        let deadline = policy.total_timeout().map(|timeout| started + timeout);
+        let mut bytes = Vec::new();
+        loop {
+            let chunk = match remaining_timeout(policy.read_timeout(), deadline) {
+                Some(timeout) => tokio::time::timeout(timeout, response.chunk()).await
+                    .map_err(|_| format!("Timed out after {} ms reading response body", timeout.as_millis()))?,
+                None => response.chunk().await,
+            };
+            match chunk.map_err(|e| format!("Failed to read response body: {}", e))? {
+                Some(chunk) => bytes.extend_from_slice(&chunk),
+                None => break,
+            }
+        }
+        let body = match response_type {
+            ResponseType::Text => ResponseBody::Text(String::from_utf8_lossy(&bytes).into_owned()),
+            ResponseType::Json => ResponseBody::Json(serde_json::from_slice(&bytes)
+                .map_err(|e| format!("Failed to parse JSON response: {}", e))?),
+            ResponseType::Bytes => ResponseBody::Bytes(bytes),
+            ResponseType::Auto => auto_body(&content_type, bytes),
+        };
+
+        Ok(ProxyResponse {
+            status: status.as_u16(),
+            ok: status.is_success(),
+            headers,
+            url,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+            body,
+        })
+    }
+}
+
+// 根据 Content-Type 判断响应体格式，无法解析的 JSON 按文本返回，未知类型按二进制返回
+fn auto_body(content_type: &str, bytes: Vec<u8>) -> ResponseBody {
+    let mime = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+    let is_json = mime == "application/json" || mime.ends_with("+json");
+    if is_json {
+        if let Ok(value) = serde_json::from_slice(&bytes) {
+            return ResponseBody::Json(value);
+        }
+    }
+
+    let is_text = is_json
+        || mime.starts_with("text/")
+        || mime.ends_with("+xml")
+        || matches!(mime.as_str(), "application/xml" | "application/javascript" | "application/x-www-form-urlencoded")
+        || content_type.to_ascii_lowercase().contains("charset=");
+    // 没有 Content-Type 但内容是合法 UTF-8 时也按文本处理
+    if is_text || (mime.is_empty() && std::str::from_utf8(&bytes).is_ok()) {
+        return ResponseBody::Text(String::from_utf8_lossy(&bytes).into_owned());
+    }
+    ResponseBody::Bytes(bytes)
+}
+
 // HTTP客户端结构体定义
 pub struct HttpClient {
-    client: Client,
+    // 按连接超时时间缓存的客户端，reqwest 只能在创建客户端时设置连接超时
+    clients: Mutex<HashMap<u64, Client>>,
+    // 没有单独指定策略的请求使用的默认策略
+    default_policy: Mutex<RequestPolicy>,
 }
 
 // 实现HTTP客户端的方法
@@ -23,8 +152,58 @@ impl HttpClient {
             MODEL_NAME.to_string(),
         );
         Self {
-            client: Client::new()
+            clients: Mutex::new(HashMap::new()),
+            default_policy: Mutex::new(RequestPolicy::default()),
+        }
+    }
+
+    fn lock_default_policy(&self) -> Result<std::sync::MutexGuard<'_, RequestPolicy>, String> {
+        self.default_policy.lock().map_err(|e| {
+            let error_msg = format!("Failed to lock request policy: {}", e);
+            log_message(error_msg.clone(), "ERROR".to_string(), MODEL_NAME.to_string());
+            error_msg
+        })
+    }
+
+    pub fn default_policy(&self) -> Result<RequestPolicy, String> {
+        Ok(self.lock_default_policy()?.clone())
+    }
+
+    // 更新默认的超时和重试策略
+    pub fn set_default_policy(&self, policy: RequestPolicy) -> Result<(), String> {
+        log_message(
+            format!("Updating default request policy: {:?}", policy),
+            "INFO".to_string(),
+            MODEL_NAME.to_string(),
+        );
+        policy.validate()?;
+        *self.lock_default_policy()? = policy;
+        Ok(())
+    }
+
+    // 在默认策略上应用单个请求的覆盖项
+    pub fn policy(&self, policy: Option<RequestPolicyOverride>) -> Result<RequestPolicy, String> {
+        let default_policy = self.default_policy()?;
+        match policy {
+            Some(policy) => policy.apply(&default_policy),
+            None => Ok(default_policy),
+        }
+    }
+
+    fn client_for(&self, policy: &RequestPolicy) -> Result<Client> {
+        let mut clients = self.clients.lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock HTTP clients: {}", e))?;
+        if let Some(client) = clients.get(&policy.connect_timeout_ms) {
+            return Ok(client.clone());
+        }
+
+        let mut builder = Client::builder();
+        if let Some(timeout) = policy.connect_timeout() {
+            builder = builder.connect_timeout(timeout);
         }
+        let client = builder.build()?;
+        clients.insert(policy.connect_timeout_ms, client.clone());
+        Ok(client)
     }
 
     // 代理HTTP请求的核心方法
@@ -33,55 +212,94 @@ impl HttpClient {
         target_url: &str,
         method: &str,
         body: Vec<u8>,
+        policy: &RequestPolicy,
     ) -> Result<Response> {
         log_message(
             format!("Proxying {} request to {}", method, target_url),
             "DEBUG".to_string(),
             MODEL_NAME.to_string(),
         );
-    
-        // 设置multipart表单的boundary
-        let boundary = "----WebKitFormBoundary7MA4YWxkTrZu0gW";
-        // 根据HTTP方法构建请求
-        let request = match method {
-            "GET" => self.client.get(target_url),
-            "POST" => {
-                let content_type = format!("multipart/form-data; boundary={}", boundary);
-                log_message(
-                    format!("Setting Content-Type: {}", content_type),
-                    "DEBUG".to_string(),
-                    MODEL_NAME.to_string(),
-                );
-                
-                // 构建POST请求,设置Content-Type和请求体
-                self.client.post(target_url)
-                    .header("Content-Type", content_type)
-                    .body(body)
-            },
-            _ => {
-                // 处理不支持的HTTP方法
-                log_message(
-                    format!("Unsupported HTTP method: {}", method),
-                    "ERROR".to_string(),
-                    MODEL_NAME.to_string(),
-                );
-                return Err(anyhow::anyhow!("Unsupported HTTP method"));
+
+        // 不设置 Content-Type，需要指定时使用 proxy_request_with_headers，表单使用 send_multipart
+        self.execute(parse_method(method)?, target_url, &HashMap::new(), body, policy).await
+    }
+
+    // 按策略发送请求，超时、连接失败或返回可重试的状态码时按指数退避重试
+    //
+    // 非幂等的请求只在策略允许时重试；Retry-After 超过退避上限或剩余总时间时直接返回该响应
+    async fn execute(
+        &self,
+        method: Method,
+        target_url: &str,
+        headers: &HashMap<String, String>,
+        body: Vec<u8>,
+        policy: &RequestPolicy,
+    ) -> Result<Response> {
+        let client = self.client_for(policy)?;
+        let deadline = policy.total_timeout().map(|timeout| Instant::now() + timeout);
+        let max_attempts = policy.max_attempts(&method);
+        let mut attempt = 0;
+
+        loop {
+            attempt += 1;
+            // 只在有请求体或方法需要请求体时才附带
+            let mut request = client.request(method.clone(), target_url);
+            for (key, value) in headers {
+                request = request.header(key, value);
+            }
+            if !body.is_empty() || matches!(method, Method::POST | Method::PUT | Method::PATCH) {
+                request = request.body(body.clone());
             }
-        };
-    
-        // 发送请求并获取响应
-        let response = request.send().await?;
-        log_message(
-            format!(
-                "Received response: Status={}, Content-Length={:?}",
-                response.status(),
-                response.headers().get("content-length")
-            ),
-            "DEBUG".to_string(),
-            MODEL_NAME.to_string(),
-        );
 
-        Ok(response)
+            let result = match remaining_timeout(policy.read_timeout(), deadline) {
+                Some(timeout) => match tokio::time::timeout(timeout, request.send()).await {
+                    Ok(result) => result.map_err(anyhow::Error::from),
+                    Err(_) => Err(anyhow::anyhow!("Timed out after {} ms waiting for response", timeout.as_millis())),
+                },
+                None => request.send().await.map_err(anyhow::Error::from),
+            };
+
+            let delay = match &result {
+                Ok(response) if policy.should_retry_status(response.status()) => {
+                    match response.headers().get(RETRY_AFTER).and_then(|value| value.to_str().ok()).and_then(parse_retry_after) {
+                        Some(retry_after) if retry_after > policy.max_backoff() => None,
+                        Some(retry_after) => Some(retry_after),
+                        None => Some(policy.backoff(attempt)),
+                    }
+                }
+                Err(e) if is_transient(e) => Some(policy.backoff(attempt)),
+                _ => None,
+            };
+            let delay = delay
+                .filter(|_| attempt < max_attempts)
+                .filter(|delay| deadline.is_none_or(|deadline| Instant::now() + *delay < deadline));
+
+            let Some(delay) = delay else {
+                if let Ok(response) = &result {
+                    log_message(
+                        format!(
+                            "Received response: Status={}, Content-Length={:?}",
+                            response.status(),
+                            response.headers().get("content-length")
+                        ),
+                        "DEBUG".to_string(),
+                        MODEL_NAME.to_string(),
+                    );
+                }
+                return result;
+            };
+
+            let reason = match &result {
+                Ok(response) => format!("status {}", response.status()),
+                Err(e) => e.to_string(),
+            };
+            log_message(
+                format!("{} {} failed with {} (attempt {}/{}), retrying in {} ms", method, target_url, reason, attempt, max_attempts, delay.as_millis()),
+                "WARN".to_string(),
+                MODEL_NAME.to_string(),
+            );
+            tokio::time::sleep(delay).await;
+        }
     }
 
     // 发送请求并返回响应文本的方法
@@ -99,12 +317,13 @@ impl HttpClient {
         );
 
         // 发送请求并处理错误
-        let response = self.proxy_request(target_url, method, body).await
+        let policy = self.policy(None)?;
+        let started = Instant::now();
+        let response = self.proxy_request(target_url, method, body, &policy).await
             .map_err(|e| format!("Request failed: {}", e))?;
-            
+
         // 将响应转换为文本
-        response.text().await
-            .map_err(|e| format!("Failed to parse response: {}", e))
+        Ok(ProxyResponse::read(response, started, ResponseType::Text, &policy).await?.body.into_text())
     }
 
     // 检查URL状态的方法
@@ -117,7 +336,7 @@ impl HttpClient {
         );
 
         // 发送GET请求检查状态
-        let response = self.proxy_request(url, "GET", vec![]).await
+        let response = self.proxy_request(url, "GET", vec![], &self.policy(None)?).await
             .map_err(|e| format!("Status check failed: {}", e))?;
             
         // 返回状态码是否为200
@@ -128,8 +347,9 @@ impl HttpClient {
         &self,
         target_url: &str,
         method: &str,
-        headers: std::collections::HashMap<String, String>,
+        headers: HashMap<String, String>,
         body: Vec<u8>,
+        policy: &RequestPolicy,
     ) -> Result<Response> {
         log_message(
             format!("Proxying {} request to {} with headers", method, target_url),
@@ -138,55 +358,25 @@ impl HttpClient {
         );
 
         // 根据HTTP方法构建请求
-        let mut request_builder = match method {
-            "GET" => self.client.get(target_url),
-            "POST" => self.client.post(target_url),
-            _ => {
-                log_message(
-                    format!("Unsupported HTTP method: {}", method),
-                    "ERROR".to_string(),
-                    MODEL_NAME.to_string(),
-                );
-                return Err(anyhow::anyhow!("Unsupported HTTP method"));
-            }
-        };
+        let method = parse_method(method)?;
 
-        // 添加headers
-        for (key, value) in headers {
-            // 在日志记录前先克隆值
-            let key_clone = key.clone();
-            let value_clone = value.clone();
-            
+        // 记录headers，凭据类的值不写入日志
+        for (key, value) in &headers {
             log_message(
-                format!("Adding header: {} = {}", key_clone, value_clone),
+                format!("Adding header: {} = {}", key, loggable_header_value(key, value)),
                 "DEBUG".to_string(),
                 MODEL_NAME.to_string(),
             );
-
-            request_builder = request_builder.header(key, value);
         }
 
-        // 添加body并发送请求
-        let response = request_builder.body(body).send().await?;
-        
-        log_message(
-            format!(
-                "Received response: Status={}, Content-Length={:?}",
-                response.status(),
-                response.headers().get("content-length")
-            ),
-            "INFO".to_string(),
-            MODEL_NAME.to_string(),
-        );
-
-        Ok(response)
+        self.execute(method, target_url, &headers, body, policy).await
     }
 
     pub async fn send_request_with_headers(
         &self,
         target_url: &str,
         method: &str,
-        headers: std::collections::HashMap<String, String>,
+        headers: HashMap<String, String>,
         body: Vec<u8>
     ) -> Result<String, String> {
         let function_name = "send_request_with_headers";
@@ -196,10 +386,128 @@ impl HttpClient {
             MODEL_NAME.to_string(),
         );
 
-        let response = self.proxy_request_with_headers(target_url, method, headers, body).await
+        let response = self.fetch(target_url, method, headers, body, ResponseType::Text, &self.policy(None)?).await?;
+        Ok(response.body.into_text())
+    }
+
+    // 发送 multipart/form-data 请求，Content-Type 由表单的 boundary 决定，会覆盖 headers 中的同名项
+    pub async fn send_multipart(
+        &self,
+        target_url: &str,
+        method: &str,
+        mut headers: HashMap<String, String>,
+        form: MultipartForm,
+        response_type: ResponseType,
+        policy: &RequestPolicy,
+    ) -> Result<ProxyResponse, String> {
+        headers.retain(|name, _| !name.eq_ignore_ascii_case("content-type"));
+        headers.insert("Content-Type".to_string(), form.content_type());
+        self.fetch(target_url, method, headers, form.finish(), response_type, policy).await
+    }
+
+    // 发送请求并返回包含状态码、响应头和响应体的完整响应，非 2xx 的响应同样正常返回
+    pub async fn fetch(
+        &self,
+        target_url: &str,
+        method: &str,
+        headers: HashMap<String, String>,
+        body: Vec<u8>,
+        response_type: ResponseType,
+        policy: &RequestPolicy,
+    ) -> Result<ProxyResponse, String> {
+        let function_name = "fetch";
+        log_message(
+            format!("[{}] Sending {} request to {}", function_name, method, target_url),
+            "DEBUG".to_string(),
+            MODEL_NAME.to_string(),
+        );
+
+        let started = Instant::now();
+        let response = self.proxy_request_with_headers(target_url, method, headers, body, policy).await
             .map_err(|e| format!("Request failed: {}", e))?;
-            
-        response.text().await
-            .map_err(|e| format!("Failed to parse response: {}", e))
+        let response = ProxyResponse::read(response, started, response_type, policy).await?;
+
+        log_message(
+            format!("[{}] {} {} returned {} in {} ms", function_name, method, response.url, response.status, response.elapsed_ms),
+            "DEBUG".to_string(),
+            MODEL_NAME.to_string(),
+        );
+        Ok(response)
+    }
+}
+
+// 解析HTTP方法名称，不区分大小写
+fn parse_method(method: &str) -> Result<Method> {
+    match method.trim().to_ascii_uppercase().as_str() {
+        "GET" => Ok(Method::GET),
+        "POST" => Ok(Method::POST),
+        "PUT" => Ok(Method::PUT),
+        "PATCH" => Ok(Method::PATCH),
+        "DELETE" => Ok(Method::DELETE),
+        "HEAD" => Ok(Method::HEAD),
+        "OPTIONS" => Ok(Method::OPTIONS),
+        _ => {
+            // 处理不支持的HTTP方法
+            log_message(
+                format!("Unsupported HTTP method: {}", method),
+                "ERROR".to_string(),
+                MODEL_NAME.to_string(),
+            );
+            Err(anyhow::anyhow!("Unsupported HTTP method: {}", method))
+        }
+    }
+}
+
+// 日志中使用的请求头值，Authorization、Cookie 和各类 API key 只记录为 [redacted]
+fn loggable_header_value<'a>(name: &str, value: &'a str) -> &'a str {
+    let name = name.trim().to_ascii_lowercase();
+    let sensitive = matches!(name.as_str(), "authorization" | "proxy-authorization" | "cookie")
+        || name.replace('_', "-").contains("api-key");
+    if sensitive {
+        "[redacted]"
+    } else {
+        value
+    }
+}
+
+// 本次等待的超时时间：读取超时和距总截止时间的剩余时间中较短的一个
+fn remaining_timeout(read_timeout: Option<Duration>, deadline: Option<Instant>) -> Option<Duration> {
+    let remaining = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+    match (read_timeout, remaining) {
+        (Some(read_timeout), Some(remaining)) => Some(read_timeout.min(remaining)),
+        (read_timeout, remaining) => read_timeout.or(remaining),
+    }
+}
+
+// 超时和连接失败属于暂时性错误，execute 自身产生的错误只有等待响应超时
+fn is_transient(error: &anyhow::Error) -> bool {
+    match error.downcast_ref::<reqwest::Error>() {
+        Some(e) => e.is_timeout() || e.is_connect(),
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn credential_headers_are_redacted_in_logs() {
+        assert_eq!(loggable_header_value("Proxy-Authorization", "Basic dXNlcjpwYXNz"), "[redacted]");
+        assert_eq!(loggable_header_value("Cookie", "session=abc"), "[redacted]");
+        assert_eq!(loggable_header_value("X-Api-Key", "secret"), "[redacted]");
+        assert_eq!(loggable_header_value("Accept", "application/json"), "application/json");
+    }
+    // tts.ts 的声音克隆等请求通过 proxy_request_with_headers 发送 Bearer 令牌
+    #[test]
+    fn tts_request_headers_do_not_leak_the_api_key() {
+        let headers = HashMap::from([
+            ("Authorization".to_string(), "Bearer sk-test".to_string()),
+            ("api_key".to_string(), "sk-test".to_string()),
+            ("Content-Type".to_string(), "application/json".to_string()),
+        ]);
+        for (key, value) in &headers {
+            assert!(!loggable_header_value(key, value).contains("sk-test"), "{}", key);
+        }
     }
 }
@@ -0,0 +1,365 @@
+// 引入必要的外部依赖
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tokio::sync::oneshot;
+
+// 引入本地模块
+use crate::commands::log_message;
+use crate::events::{self, HTTP_STREAM_CHUNK, HTTP_STREAM_END, HTTP_STREAM_ERROR, HTTP_STREAM_EVENT};
+use crate::http_client::HttpClient;
+use crate::request_policy::RequestPolicy;
+
+// 定义模块名称常量
+const MODEL_NAME: &str = "HttpStream";
+
+// 正在进行的流式请求，发送端用于取消对应的请求
+static STREAMS: Lazy<Mutex<HashMap<String, oneshot::Sender<()>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+static NEXT_STREAM_ID: AtomicU64 = AtomicU64::new(1);
+
+// http-stream-chunk 事件的负载，data 为按 UTF-8 解码的响应体片段
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamChunk {
+    pub stream_id: String,
+    pub data: String,
+}
+
+// http-stream-event 事件的负载，响应为 text/event-stream 时每个 SSE 事件发送一次
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamEvent {
+    pub stream_id: String,
+    // SSE 的 event 字段，未指定时为空(即默认的 message 事件)
+    pub event: Option<String>,
+    // 多行 data 以换行连接
+    pub data: String,
+    pub id: Option<String>,
+}
+
+// http-stream-end 事件的负载
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamEnd {
+    pub stream_id: String,
+    // HTTP 状态码，非 2xx 的响应体同样会以 chunk 事件发送
+    pub status: u16,
+    pub bytes: u64,
+    // 是否被 cancel_stream_request 提前结束
+    pub cancelled: bool,
+}
+
+// http-stream-error 事件的负载
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamError {
+    pub stream_id: String,
+    pub error: String,
+}
+
+// 开始一个流式请求并立即返回流编号，响应体通过事件发送，最后发送 end 或 error 事件
+//
+// 前端应在调用前注册事件监听，请求可能在编号返回之前就已产生事件
+//
+// 策略的总超时只限制到收到响应头为止，之后每个片段之间的等待受读取超时限制
+pub fn start_stream(
+    client: &'static HttpClient,
+    target_url: String,
+    method: String,
+    headers: HashMap<String, String>,
+    body: Vec<u8>,
+    policy: RequestPolicy,
+) -> Result<String, String> {
+    let stream_id = format!("stream-{}", NEXT_STREAM_ID.fetch_add(1, Ordering::Relaxed));
+    let (cancel_sender, cancel) = oneshot::channel();
+    lock_streams()?.insert(stream_id.clone(), cancel_sender);
+
+    log_message(
+        format!("Starting stream {} for {} {}", stream_id, method, target_url),
+        "INFO".to_string(),
+        MODEL_NAME.to_string(),
+    );
+
+    let id = stream_id.clone();
+    tauri::async_runtime::spawn(async move {
+        let request = client.proxy_request_with_headers(&target_url, &method, headers, body, &policy);
+        match run_stream(&id, request, &policy, cancel).await {
+            Ok(end) => {
+                log_message(format!("Stream {} finished: {:?}", id, end), "INFO".to_string(), MODEL_NAME.to_string());
+                events::emit(HTTP_STREAM_END, end);
+            }
+            Err(error) => {
+                log_message(format!("Stream {} failed: {}", id, error), "ERROR".to_string(), MODEL_NAME.to_string());
+                events::emit(HTTP_STREAM_ERROR, StreamError { stream_id: id.clone(), error });
+            }
+        }
+        if let Ok(mut streams) = lock_streams() {
+            streams.remove(&id);
+        }
+    });
+
+    Ok(stream_id)
+}
+
+// 取消正在进行的流式请求，返回该请求是否仍在进行
+pub fn cancel_stream(stream_id: &str) -> Result<bool, String> {
+    log_message(format!("Cancelling stream {}", stream_id), "INFO".to_string(), MODEL_NAME.to_string());
+    Ok(match lock_streams()?.remove(stream_id) {
+        Some(cancel) => cancel.send(()).is_ok(),
+        None => false,
+    })
+}
+
+fn lock_streams() -> Result<std::sync::MutexGuard<'static, HashMap<String, oneshot::Sender<()>>>, String> {
+    STREAMS.lock().map_err(|e| {
+        let error_msg = format!("Failed to lock streams: {}", e);
+        log_message(error_msg.clone(), "ERROR".to_string(), MODEL_NAME.to_string());
+        error_msg
+    })
+}
+
+async fn run_stream(
+    stream_id: &str,
+    request: impl Future<Output = anyhow::Result<reqwest::Response>>,
+    policy: &RequestPolicy,
+    mut cancel: oneshot::Receiver<()>,
+) -> Result<StreamEnd, String> {
+    let mut response = tokio::select! {
+        response = request => {
+            response.map_err(|e| format!("Request failed: {}", e))?
+        }
+        _ = &mut cancel => return Ok(end_of(stream_id, 0, 0, true)),
+    };
+
+    let status = response.status().as_u16();
+    let is_sse = response.headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(is_event_stream);
+    let mut decoder = Utf8Decoder::default();
+    let mut parser = is_sse.then(SseParser::default);
+    let mut bytes = 0u64;
+
+    loop {
+        let chunk = tokio::select! {
+            chunk = read_chunk(&mut response, policy) => chunk?,
+            _ = &mut cancel => return Ok(end_of(stream_id, status, bytes, true)),
+        };
+        let done = chunk.is_none();
+        let text = match chunk {
+            Some(chunk) => {
+                bytes += chunk.len() as u64;
+                decoder.decode(&chunk)
+            }
+            None => decoder.finish(),
+        };
+
+        if !text.is_empty() {
+            events::emit(HTTP_STREAM_CHUNK, StreamChunk {
+                stream_id: stream_id.to_string(),
+                data: text.clone(),
+            });
+        }
+        if let Some(parser) = parser.as_mut() {
+            let mut frames = parser.push(&text);
+            if done {
+                frames.extend(parser.finish());
+            }
+            for (event, data, id) in frames {
+                events::emit(HTTP_STREAM_EVENT, StreamEvent {
+                    stream_id: stream_id.to_string(),
+                    event,
+                    data,
+                    id,
+                });
+            }
+        }
+
+        if done {
+            return Ok(end_of(stream_id, status, bytes, false));
+        }
+    }
+}
+
+// 读取下一个片段，超过读取超时仍没有数据时视为失败
+async fn read_chunk(response: &mut reqwest::Response, policy: &RequestPolicy) -> Result<Option<impl std::ops::Deref<Target = [u8]>>, String> {
+    let chunk = match policy.read_timeout() {
+        Some(timeout) => tokio::time::timeout(timeout, response.chunk()).await
+            .map_err(|_| format!("No data received for {} ms", timeout.as_millis()))?,
+        None => response.chunk().await,
+    };
+    chunk.map_err(|e| format!("Failed to read response body: {}", e))
+}
+
+fn end_of(stream_id: &str, status: u16, bytes: u64, cancelled: bool) -> StreamEnd {
+    StreamEnd {
+        stream_id: stream_id.to_string(),
+        status,
+        bytes,
+        cancelled,
+    }
+}
+
+// 按 Content-Type 的媒体类型判断是否为 SSE，忽略大小写、空白和 charset 等参数
+fn is_event_stream(content_type: &str) -> bool {
+    content_type.split(';')
+        .next()
+        .is_some_and(|media_type| media_type.trim().eq_ignore_ascii_case("text/event-stream"))
+}
+
+// 增量式 UTF-8 解码，跨片段的多字节字符留到下一个片段再解码
+#[derive(Default)]
+struct Utf8Decoder {
+    pending: Vec<u8>,
+}
+
+impl Utf8Decoder {
+    fn decode(&mut self, bytes: &[u8]) -> String {
+        self.pending.extend_from_slice(bytes);
+        let complete = match std::str::from_utf8(&self.pending) {
+            Ok(_) => self.pending.len(),
+            // 只是结尾的字符不完整，等待后续字节
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            // 其中有无效字节，按替换字符处理
+            Err(_) => self.pending.len(),
+        };
+        let text = String::from_utf8_lossy(&self.pending[..complete]).into_owned();
+        self.pending.drain(..complete);
+        text
+    }
+
+    // 响应结束时剩余的不完整字符按替换字符处理
+    fn finish(&mut self) -> String {
+        let text = String::from_utf8_lossy(&self.pending).into_owned();
+        self.pending.clear();
+        text
+    }
+}
+
+// 解析出的一个 SSE 事件: (event, data, id)
+type SseFrame = (Option<String>, String, Option<String>);
+
+// 增量式 SSE 解析器，事件以空行结束
+#[derive(Default)]
+struct SseParser {
+    buffer: String,
+    event: Option<String>,
+    data: Vec<String>,
+    // 最近一次收到的事件 id，按规范在后续事件中沿用
+    id: Option<String>,
+}
+
+impl SseParser {
+    fn push(&mut self, text: &str) -> Vec<SseFrame> {
+        self.buffer.push_str(text);
+        let mut frames = Vec::new();
+        while let Some(end) = self.buffer.find('\n') {
+            let raw: String = self.buffer.drain(..=end).collect();
+            let line = raw.trim_end_matches(['\n', '\r']);
+            if line.is_empty() {
+                frames.extend(self.dispatch());
+            } else {
+                self.field(line);
+            }
+        }
+        frames
+    }
+
+    // 响应结束时处理最后一个没有以空行结尾的事件
+    fn finish(&mut self) -> Option<SseFrame> {
+        let rest = std::mem::take(&mut self.buffer);
+        let line = rest.trim_end_matches('\r');
+        if !line.is_empty() {
+            self.field(line);
+        }
+        self.dispatch()
+    }
+
+    fn field(&mut self, line: &str) {
+        // 以冒号开头的是注释，常用作心跳
+        if line.starts_with(':') {
+            return;
+        }
+        let (name, value) = match line.split_once(':') {
+            Some((name, value)) => (name, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match name {
+            "event" => self.event = Some(value.to_string()),
+            "data" => self.data.push(value.to_string()),
+            "id" => self.id = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    fn dispatch(&mut self) -> Option<SseFrame> {
+        let event = self.event.take();
+        if self.data.is_empty() {
+            return None;
+        }
+        let data = std::mem::take(&mut self.data).join("\n");
+        Some((event, data, self.id.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_stream_media_type_is_parsed() {
+        assert!(is_event_stream("text/event-stream"));
+        assert!(is_event_stream("Text/Event-Stream; charset=utf-8"));
+        assert!(is_event_stream("  text/event-stream ;charset=UTF-8"));
+        assert!(!is_event_stream("text/event-streaming"));
+        assert!(!is_event_stream("application/json"));
+        assert!(!is_event_stream(""));
+    }
+
+    #[test]
+    fn utf8_decoder_keeps_split_characters_for_the_next_chunk() {
+        let mut decoder = Utf8Decoder::default();
+        let bytes = "舵机".as_bytes();
+        assert_eq!(decoder.decode(&bytes[..2]), "");
+        assert_eq!(decoder.decode(&bytes[2..4]), "舵");
+        assert_eq!(decoder.decode(&bytes[4..]), "机");
+        assert_eq!(decoder.finish(), "");
+    }
+
+    #[test]
+    fn utf8_decoder_replaces_invalid_bytes() {
+        let mut decoder = Utf8Decoder::default();
+        assert_eq!(decoder.decode(b"a\xffb"), "a\u{FFFD}b");
+        assert_eq!(decoder.decode(&"机".as_bytes()[..1]), "");
+        assert_eq!(decoder.finish(), "\u{FFFD}");
+    }
+
+    #[test]
+    fn sse_parser_splits_events_across_chunks() {
+        let mut parser = SseParser::default();
+        assert!(parser.push("event: pose\r\ndata: {\"x\"").is_empty());
+        let frames = parser.push(": 90}\r\ndata: line 2\r\n\r\n");
+        assert_eq!(frames, vec![(Some("pose".to_string()), "{\"x\": 90}\nline 2".to_string(), None)]);
+    }
+
+    #[test]
+    fn sse_parser_skips_comments_and_keeps_the_last_id() {
+        let mut parser = SseParser::default();
+        let frames = parser.push(": keep-alive\n\nid: 7\ndata: first\n\ndata: second\n\n");
+        assert_eq!(frames, vec![
+            (None, "first".to_string(), Some("7".to_string())),
+            (None, "second".to_string(), Some("7".to_string())),
+        ]);
+    }
+
+    #[test]
+    fn sse_parser_dispatches_the_last_event_on_finish() {
+        let mut parser = SseParser::default();
+        assert!(parser.push("data: tail").is_empty());
+        assert_eq!(parser.finish(), Some((None, "tail".to_string(), None)));
+        assert_eq!(parser.finish(), None);
+    }
+}
@@ -6,8 +6,28 @@
 mod commands;
 mod device_manager;
 mod servo_controller;
+mod servo_worker;
+mod motion_planner;
+mod calibration;
+mod safety;
+mod gesture;
+mod app_data;
+mod protocol;
+mod discovery;
 mod logger;
 mod http_client;
+mod http_stream;
+mod multipart;
+mod request_policy;
+mod transport;
+mod events;
+mod hotplug;
+mod reconnect;
+mod random;
+mod device_state;
+mod profiles;
+mod groups;
+mod virtual_device;
 
 use crate::logger::setup_logging;
 use crate::device_manager::DeviceManager;
@@ -22,11 +42,26 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let app_state = commands::AppState {
         device_manager: device_manager.clone(), 
     };
+    let setup_device_manager = device_manager.clone();
 
     tauri::Builder::default()
         .manage(app_state)
-        .setup(|app| {
+        .setup(move |app| {
             setup_logging().expect("Failed to setup logging");
+            events::init(app.handle());
+            // 从应用数据目录加载持久化的设备数据
+            match app.path_resolver().app_data_dir() {
+                Some(data_dir) => {
+                    if let Err(e) = setup_device_manager.set_data_dir(data_dir) {
+                        commands::log_message(format!("Failed to load device data: {}", e), "ERROR".to_string(), "main".to_string());
+                    }
+                }
+                None => commands::log_message("App data directory is unavailable, device data will not be persisted".to_string(), "WARN".to_string(), "main".to_string()),
+            }
+            // 监听串口插拔
+            hotplug::spawn_watcher(setup_device_manager.clone());
+            // 定期向前端推送设备状态
+            setup_device_manager.spawn_state_events();
             #[cfg(debug_assertions)]
             {
                 let window = app.get_window("main").unwrap();
@@ -36,14 +71,48 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         })
         .invoke_handler(tauri::generate_handler![
             commands::set_servo_position,
+            commands::set_servo_channels,
             commands::check_device_status,
+            commands::ping_device,
+            commands::get_profiles,
+            commands::set_profile,
+            commands::delete_profile,
+            commands::get_groups,
+            commands::set_group,
+            commands::delete_group,
+            commands::set_group_position,
+            commands::play_group_gesture,
+            commands::get_device_state,
+            commands::list_devices,
+            commands::connect_device,
+            commands::disconnect_device,
+            commands::disconnect_all,
+            commands::emergency_stop,
+            commands::clear_emergency_stop,
+            commands::is_emergency_stopped,
+            commands::get_safety_envelope,
+            commands::set_safety_envelope,
+            commands::play_gesture,
+            commands::list_gestures,
+            commands::get_motion_config,
+            commands::set_motion_config,
+            commands::get_link_stats,
+            commands::get_calibration,
+            commands::set_calibration,
             commands::greet,
             commands::log_message,
             commands::get_logs,
             commands::clear_logs,
             commands::get_serial_ports,
+            commands::discover_devices,
             commands::proxy_request,
             commands::proxy_request_with_headers,
+            commands::proxy_fetch,
+            commands::proxy_multipart_request,
+            commands::proxy_stream_request,
+            commands::cancel_stream_request,
+            commands::get_request_policy,
+            commands::set_request_policy,
             commands::check_server_status,
         ])
         .run(tauri::generate_context!())
@@ -0,0 +1,221 @@
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+
+// 运动曲线类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MotionProfile {
+    // 匀速
+    Linear,
+    // 正弦加减速
+    EaseInOut,
+    // 五次多项式，起止处速度和加速度都为零
+    SCurve,
+}
+
+impl MotionProfile {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.to_ascii_lowercase().replace('_', "-").as_str() {
+            "linear" => Ok(MotionProfile::Linear),
+            "ease-in-out" | "easeinout" => Ok(MotionProfile::EaseInOut),
+            "s-curve" | "scurve" => Ok(MotionProfile::SCurve),
+            _ => Err(format!("Unknown motion profile: {}", name)),
+        }
+    }
+
+    // 将归一化时间 t (0..=1) 映射为归一化位移
+    fn ease(self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            MotionProfile::Linear => t,
+            MotionProfile::EaseInOut => (1.0 - (std::f64::consts::PI * t).cos()) / 2.0,
+            MotionProfile::SCurve => t * t * t * (t * (t * 6.0 - 15.0) + 10.0),
+        }
+    }
+
+    // 曲线导数的峰值，用于根据最大速度求最短时长
+    fn peak_velocity_factor(self) -> f64 {
+        match self {
+            MotionProfile::Linear => 1.0,
+            MotionProfile::EaseInOut => std::f64::consts::PI / 2.0,
+            MotionProfile::SCurve => 15.0 / 8.0,
+        }
+    }
+
+    // 曲线二阶导数的峰值，匀速曲线不受加速度约束
+    fn peak_acceleration_factor(self) -> Option<f64> {
+        match self {
+            MotionProfile::Linear => None,
+            MotionProfile::EaseInOut => Some(std::f64::consts::PI * std::f64::consts::PI / 2.0),
+            MotionProfile::SCurve => Some(10.0 / 3.0_f64.sqrt()),
+        }
+    }
+}
+
+// 运动规划参数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct MotionConfig {
+    // 插值周期
+    pub tick_ms: u64,
+    // 最大角速度(度/秒)
+    pub max_velocity: f64,
+    // 最大角加速度(度/秒²)
+    pub max_acceleration: f64,
+    // 未指定曲线时使用的默认曲线
+    pub default_profile: MotionProfile,
+}
+
+impl Default for MotionConfig {
+    fn default() -> Self {
+        MotionConfig {
+            tick_ms: 20,
+            max_velocity: 180.0,
+            max_acceleration: 720.0,
+            default_profile: MotionProfile::EaseInOut,
+        }
+    }
+}
+
+impl MotionConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.tick_ms == 0 {
+            return Err("tickMs must be greater than zero".to_string());
+        }
+        let positive = |v: f64| v.is_finite() && v > 0.0;
+        if !positive(self.max_velocity) || !positive(self.max_acceleration) {
+            return Err("maxVelocity and maxAcceleration must be positive".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn tick(&self) -> Duration {
+        Duration::from_millis(self.tick_ms)
+    }
+}
+
+// 从起始姿态到目标姿态的一段轨迹，各轴同时出发、同时到达
+#[derive(Debug, Clone)]
+pub struct Trajectory {
+    start: Vec<f64>,
+    target: Vec<f64>,
+    duration: Duration,
+    profile: MotionProfile,
+}
+
+impl Trajectory {
+    // 规划轨迹，请求的时长不足以满足速度和加速度限制时会被延长
+    //
+    // 角度无效或算出的时长无法表示时返回错误
+    pub fn plan(
+        start: &[f64],
+        target: &[f64],
+        requested: Option<Duration>,
+        profile: MotionProfile,
+        config: &MotionConfig,
+    ) -> Result<Self, String> {
+        if start.iter().chain(target).any(|angle| !angle.is_finite()) {
+            return Err(format!("Cannot plan a motion from {:?} to {:?}", start, target));
+        }
+
+        let distance = start.iter()
+            .zip(target)
+            .map(|(a, b)| (b - a).abs())
+            .fold(0.0, f64::max);
+
+        let mut seconds = distance * profile.peak_velocity_factor() / config.max_velocity;
+        if let Some(factor) = profile.peak_acceleration_factor() {
+            seconds = seconds.max((distance * factor / config.max_acceleration).sqrt());
+        }
+        if let Some(requested) = requested {
+            seconds = seconds.max(requested.as_secs_f64());
+        }
+
+        let duration = Duration::try_from_secs_f64(seconds)
+            .map_err(|e| format!("Cannot plan a motion lasting {} s: {}", seconds, e))?;
+        Ok(Trajectory {
+            start: start.to_vec(),
+            target: target.to_vec(),
+            duration,
+            profile,
+        })
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    // 把轨迹放慢到至少 duration，用于让多台设备同时到达；放慢不会超出速度限制
+    pub fn stretch_to(&mut self, duration: Duration) {
+        self.duration = self.duration.max(duration);
+    }
+
+    // 计算轨迹开始后 elapsed 时刻的姿态
+    pub fn sample(&self, elapsed: Duration) -> Vec<f64> {
+        if self.duration.is_zero() || elapsed >= self.duration {
+            return self.target.clone();
+        }
+
+        let progress = self.profile.ease(elapsed.as_secs_f64() / self.duration.as_secs_f64());
+        self.start.iter()
+            .zip(&self.target)
+            .map(|(a, b)| a + (b - a) * progress)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan(start: &[f64], target: &[f64], requested: Option<Duration>, profile: MotionProfile) -> Result<Trajectory, String> {
+        Trajectory::plan(start, target, requested, profile, &MotionConfig::default())
+    }
+
+    #[test]
+    fn duration_respects_velocity_and_acceleration_limits() {
+        // 匀速曲线只受速度限制: 90° / 180°/s
+        let linear = plan(&[0.0], &[90.0], None, MotionProfile::Linear).unwrap();
+        assert_eq!(linear.duration(), Duration::from_millis(500));
+
+        // 短距离的正弦曲线由加速度限制决定: sqrt(10 * π²/2 / 720)
+        let eased = plan(&[0.0], &[10.0], None, MotionProfile::EaseInOut).unwrap();
+        let expected = (10.0 * std::f64::consts::PI.powi(2) / 2.0 / 720.0).sqrt();
+        assert!((eased.duration().as_secs_f64() - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn requested_duration_only_lengthens_motion() {
+        let slow = plan(&[0.0], &[90.0], Some(Duration::from_secs(2)), MotionProfile::Linear).unwrap();
+        assert_eq!(slow.duration(), Duration::from_secs(2));
+        let fast = plan(&[0.0], &[90.0], Some(Duration::from_millis(10)), MotionProfile::Linear).unwrap();
+        assert_eq!(fast.duration(), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn samples_start_and_end_on_the_endpoints() {
+        let mut trajectory = plan(&[0.0, 180.0], &[90.0, 90.0], None, MotionProfile::SCurve).unwrap();
+        assert_eq!(trajectory.sample(Duration::ZERO), vec![0.0, 180.0]);
+        let middle = trajectory.sample(trajectory.duration() / 2);
+        assert!((middle[0] - 45.0).abs() < 1e-9 && (middle[1] - 135.0).abs() < 1e-9);
+        assert_eq!(trajectory.sample(trajectory.duration()), vec![90.0, 90.0]);
+
+        trajectory.stretch_to(Duration::from_secs(5));
+        assert_eq!(trajectory.duration(), Duration::from_secs(5));
+        trajectory.stretch_to(Duration::from_secs(1));
+        assert_eq!(trajectory.duration(), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn unrepresentable_motion_is_an_error() {
+        assert!(plan(&[0.0], &[1e308], None, MotionProfile::Linear).is_err());
+        assert!(plan(&[0.0], &[f64::NAN], None, MotionProfile::EaseInOut).is_err());
+    }
+
+    #[test]
+    fn profile_names_are_parsed_loosely() {
+        assert_eq!(MotionProfile::parse("S_Curve"), Ok(MotionProfile::SCurve));
+        assert_eq!(MotionProfile::parse("easeInOut"), Ok(MotionProfile::EaseInOut));
+        assert!(MotionProfile::parse("bounce").is_err());
+    }
+}
@@ -0,0 +1,210 @@
+// 引入必要的外部依赖
+use std::path::Path;
+use serde::Deserialize;
+
+// 引入本地日志模块
+use crate::commands::log_message;
+use crate::random::random_u64;
+
+// 定义模块名称常量
+const MODEL_NAME: &str = "Multipart";
+
+// 普通文本字段
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextField {
+    pub name: String,
+    pub value: String,
+}
+
+// 文件字段，内容由 bytes 直接给出或从本地 path 读取，二者只能指定一个
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilePart {
+    pub name: String,
+    pub bytes: Option<Vec<u8>>,
+    pub path: Option<String>,
+    // 未指定时使用本地文件名，直接给出内容时使用字段名
+    pub filename: Option<String>,
+    // 未指定时按文件扩展名推断
+    pub mime_type: Option<String>,
+}
+
+// multipart/form-data 请求体，每个表单使用随机生成的 boundary
+pub struct MultipartForm {
+    boundary: String,
+    body: Vec<u8>,
+}
+
+impl MultipartForm {
+    fn new() -> Self {
+        MultipartForm {
+            boundary: random_boundary(),
+            body: Vec::new(),
+        }
+    }
+
+    // 由文本字段和文件字段构建表单，文件从本地读取时属于阻塞操作
+    pub fn build(fields: Vec<TextField>, files: Vec<FilePart>) -> Result<Self, String> {
+        let mut form = MultipartForm::new();
+        for field in fields {
+            form.text(&field.name, &field.value);
+        }
+        for file in files {
+            let (bytes, default_filename) = match (file.bytes, file.path) {
+                (Some(bytes), None) => (bytes, file.name.clone()),
+                (None, Some(path)) => {
+                    let bytes = std::fs::read(&path).map_err(|e| {
+                        let error_msg = format!("Failed to read {} for field {}: {}", path, file.name, e);
+                        log_message(error_msg.clone(), "ERROR".to_string(), MODEL_NAME.to_string());
+                        error_msg
+                    })?;
+                    let filename = Path::new(&path).file_name()
+                        .map(|name| name.to_string_lossy().into_owned())
+                        .unwrap_or_else(|| file.name.clone());
+                    (bytes, filename)
+                }
+                _ => return Err(format!("File field {} needs exactly one of bytes or path", file.name)),
+            };
+            let filename = file.filename.unwrap_or(default_filename);
+            let mime_type = file.mime_type.unwrap_or_else(|| guess_mime_type(&filename).to_string());
+            form.file(&file.name, &filename, &mime_type, &bytes)?;
+        }
+
+        log_message(
+            format!("Built multipart body of {} bytes", form.body.len()),
+            "DEBUG".to_string(),
+            MODEL_NAME.to_string(),
+        );
+        Ok(form)
+    }
+
+    pub fn text(&mut self, name: &str, value: &str) {
+        self.part_header(&format!("Content-Disposition: form-data; name=\"{}\"\r\n", escape(name)));
+        self.body.extend_from_slice(value.as_bytes());
+        self.body.extend_from_slice(b"\r\n");
+    }
+
+    // 文件名和 MIME 类型会写入分段头，其中有换行时拒绝，避免注入额外的头部
+    pub fn file(&mut self, name: &str, filename: &str, mime_type: &str, bytes: &[u8]) -> Result<(), String> {
+        if filename.contains(['\r', '\n']) {
+            return Err(format!("Filename for field {} must not contain line breaks", name));
+        }
+        if mime_type.contains(['\r', '\n']) {
+            return Err(format!("MIME type for field {} must not contain line breaks", name));
+        }
+
+        self.part_header(&format!(
+            "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: {}\r\n",
+            escape(name),
+            escape(filename),
+            mime_type,
+        ));
+        self.body.extend_from_slice(bytes);
+        self.body.extend_from_slice(b"\r\n");
+        Ok(())
+    }
+
+    pub fn content_type(&self) -> String {
+        format!("multipart/form-data; boundary={}", self.boundary)
+    }
+
+    // 写入结束分隔符并返回完整的请求体
+    pub fn finish(mut self) -> Vec<u8> {
+        self.body.extend_from_slice(format!("--{}--\r\n", self.boundary).as_bytes());
+        self.body
+    }
+
+    fn part_header(&mut self, headers: &str) {
+        self.body.extend_from_slice(format!("--{}\r\n{}\r\n", self.boundary, headers).as_bytes());
+    }
+}
+
+// 生成随机 boundary
+fn random_boundary() -> String {
+    format!("----DeskyFormBoundary{:016x}{:016x}", random_u64(), random_u64())
+}
+
+// 按浏览器的做法转义字段名和文件名中的引号和换行
+fn escape(value: &str) -> String {
+    value.replace('"', "%22").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+// 按扩展名推断常见文件的 MIME 类型
+fn guess_mime_type(filename: &str) -> &'static str {
+    let extension = Path::new(filename).extension()
+        .map(|extension| extension.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "wav" => "audio/wav",
+        "mp3" => "audio/mpeg",
+        "ogg" => "audio/ogg",
+        "webm" => "audio/webm",
+        "m4a" => "audio/mp4",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "json" => "application/json",
+        "txt" => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file_part(name: &str, filename: Option<&str>, mime_type: Option<&str>) -> FilePart {
+        FilePart {
+            name: name.to_string(),
+            bytes: Some(b"RIFF".to_vec()),
+            path: None,
+            filename: filename.map(str::to_string),
+            mime_type: mime_type.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn form_contains_text_and_file_parts() {
+        let fields = vec![TextField { name: "model".to_string(), value: "whisper-1".to_string() }];
+        let form = MultipartForm::build(fields, vec![file_part("file", Some("clip.wav"), None)]).unwrap();
+        let boundary = form.boundary.clone();
+        assert_eq!(form.content_type(), format!("multipart/form-data; boundary={}", boundary));
+
+        let body = String::from_utf8(form.finish()).unwrap();
+        let expected = format!(
+            "--{b}\r\nContent-Disposition: form-data; name=\"model\"\r\n\r\nwhisper-1\r\n\
+             --{b}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"clip.wav\"\r\nContent-Type: audio/wav\r\n\r\nRIFF\r\n\
+             --{b}--\r\n",
+            b = boundary,
+        );
+        assert_eq!(body, expected);
+    }
+
+    #[test]
+    fn quotes_in_names_are_escaped() {
+        let form = MultipartForm::build(Vec::new(), vec![file_part("a\"b", Some("x\".wav"), None)]).unwrap();
+        let body = String::from_utf8(form.finish()).unwrap();
+        assert!(body.contains("name=\"a%22b\"; filename=\"x%22.wav\""));
+    }
+
+    #[test]
+    fn line_breaks_in_part_headers_are_rejected() {
+        let injected = MultipartForm::build(Vec::new(), vec![file_part("file", Some("a.wav\r\nX-Injected: 1"), None)]);
+        assert!(injected.is_err());
+        let injected = MultipartForm::build(Vec::new(), vec![file_part("file", None, Some("audio/wav\nX-Injected: 1"))]);
+        assert!(injected.is_err());
+    }
+
+    #[test]
+    fn file_parts_need_exactly_one_source() {
+        let mut part = file_part("file", None, None);
+        part.path = Some("clip.wav".to_string());
+        assert!(MultipartForm::build(Vec::new(), vec![part]).is_err());
+    }
+
+    #[test]
+    fn boundaries_differ_between_forms() {
+        assert_ne!(random_boundary(), random_boundary());
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use crate::app_data::JsonFile;
+use crate::calibration::ChannelCalibration;
+use crate::commands::log_message;
+use crate::device_manager::DeviceConfig;
+use crate::discovery::DiscoveredPort;
+
+// 定义模块名称常量
+const MODEL_NAME: &str = "profiles";
+// 设备配置档案在应用数据目录中的文件名
+pub const PROFILE_FILE: &str = "profiles.json";
+
+// 把别名对应到实际端口的匹配规则，所有指定的字段都需要匹配
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DeviceMatch {
+    // USB 序列号，最可靠的匹配方式
+    pub serial_number: Option<String>,
+    pub vid: Option<u16>,
+    pub pid: Option<u16>,
+    // 端口路径，也可以是 tcp://、loopback:// 或 virtual: 地址
+    pub path: Option<String>,
+}
+
+impl DeviceMatch {
+    fn is_empty(&self) -> bool {
+        self.serial_number.is_none() && self.vid.is_none() && self.pid.is_none() && self.path.is_none()
+    }
+
+    // 只按路径匹配时不需要枚举串口，例如网络设备
+    pub fn path_only(&self) -> Option<&str> {
+        match self {
+            DeviceMatch { serial_number: None, vid: None, pid: None, path: Some(path) } => Some(path),
+            _ => None,
+        }
+    }
+
+    pub fn matches(&self, port: &DiscoveredPort) -> bool {
+        let field_matches = |expected: &Option<String>, actual: &Option<String>| {
+            expected.as_ref().is_none_or(|expected| actual.as_ref() == Some(expected))
+        };
+
+        !self.is_empty()
+            && field_matches(&self.serial_number, &port.serial_number)
+            && self.vid.is_none_or(|vid| port.vid == Some(vid))
+            && self.pid.is_none_or(|pid| port.pid == Some(pid))
+            && self.path.as_ref().is_none_or(|path| *path == port.port_name)
+    }
+}
+
+// 一个带别名的设备档案
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceProfile {
+    pub alias: String,
+    #[serde(rename = "match")]
+    pub match_rule: DeviceMatch,
+    #[serde(default)]
+    pub config: DeviceConfig,
+    // 按通道名称索引的校准参数
+    #[serde(default)]
+    pub calibration: HashMap<String, ChannelCalibration>,
+}
+
+impl DeviceProfile {
+    fn validate(&self) -> Result<(), String> {
+        if self.alias.trim().is_empty() {
+            return Err("Profile alias must not be empty".to_string());
+        }
+        if self.match_rule.is_empty() {
+            return Err(format!("Profile {} needs at least one match rule", self.alias));
+        }
+        self.config.validate()?;
+        self.calibration.values().try_for_each(ChannelCalibration::validate)
+    }
+}
+
+// 所有设备档案，按别名索引并持久化到应用数据目录
+#[derive(Default)]
+pub struct ProfileStore {
+    profiles: JsonFile<BTreeMap<String, DeviceProfile>>,
+}
+
+impl ProfileStore {
+    // 加载时与保存时一样检查每个档案，包括其中的连接参数和校准参数
+    pub fn load(path: PathBuf) -> Result<Self, String> {
+        let profiles: JsonFile<BTreeMap<String, DeviceProfile>> = JsonFile::load(path.clone())?;
+        for profile in profiles.data().values() {
+            profile.validate()
+                .map_err(|e| format!("Invalid device profile {} in {}: {}", profile.alias, path.display(), e))?;
+        }
+
+        log_message(
+            format!("Loaded {} device profile(s) from {}", profiles.data().len(), path.display()),
+            "INFO".to_string(),
+            MODEL_NAME.to_string(),
+        );
+
+        Ok(ProfileStore { profiles })
+    }
+
+    pub fn get(&self, alias: &str) -> Option<&DeviceProfile> {
+        self.profiles.data().get(alias)
+    }
+
+    pub fn list(&self) -> Vec<DeviceProfile> {
+        self.profiles.data().values().cloned().collect()
+    }
+
+    // 第一个匹配该端口的档案
+    pub fn find_for_port(&self, port: &DiscoveredPort) -> Option<&DeviceProfile> {
+        self.profiles.data().values().find(|profile| profile.match_rule.matches(port))
+    }
+
+    pub fn set(&mut self, profile: DeviceProfile) -> Result<(), String> {
+        profile.validate()?;
+        self.profiles.update("device profiles", |profiles| {
+            profiles.insert(profile.alias.clone(), profile);
+            Ok(())
+        })
+    }
+
+    pub fn remove(&mut self, alias: &str) -> Result<bool, String> {
+        if !self.profiles.data().contains_key(alias) {
+            return Ok(false);
+        }
+        self.profiles.update("device profiles", |profiles| {
+            profiles.remove(alias);
+            Ok(true)
+        })
+    }
+
+    // 更新档案中单个通道的校准参数
+    pub fn set_calibration(&mut self, alias: &str, channel: &str, calibration: ChannelCalibration) -> Result<(), String> {
+        calibration.validate()?;
+        self.profiles.update("device profiles", |profiles| {
+            let profile = profiles.get_mut(alias)
+                .ok_or_else(|| format!("Unknown device profile: {}", alias))?;
+            profile.calibration.insert(channel.to_string(), calibration);
+            Ok(())
+        })
+    }
+}
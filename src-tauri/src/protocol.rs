@@ -0,0 +1,332 @@
+// 舵机串口协议的帧格式定义
+//
+// 帧结构: [0xA5][版本][序号][类型][长度][负载...][CRC16 高位][CRC16 低位]
+// CRC 采用 CRC-16/CCITT-FALSE，覆盖从版本到负载结束的所有字节
+
+use serde::Serialize;
+
+// 帧起始字节
+pub const FRAME_START: u8 = 0xA5;
+// 当前支持的最高协议版本
+pub const PROTOCOL_VERSION: u8 = 1;
+// 单帧负载的最大长度
+pub const MAX_PAYLOAD_LEN: usize = 64;
+
+// 帧头长度(起始字节、版本、序号、类型、长度)
+const HEADER_LEN: usize = 5;
+// 校验字段长度
+const CRC_LEN: usize = 2;
+
+// NACK 负载中的原因码
+pub const NACK_BAD_CHECKSUM: u8 = 0x01;
+pub const NACK_UNSUPPORTED: u8 = 0x02;
+pub const NACK_INVALID_PAYLOAD: u8 = 0x03;
+
+// 帧类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameKind {
+    // 连接时的版本协商，负载为主机支持的最高版本
+    Hello,
+    // 查询设备身份，ACK 负载见 parse_identity
+    Identify,
+    // 存活探测，设备立即回复 ACK，不改变舵机状态
+    Ping,
+    // 设置舵机位置，负载为若干 (通道序号, 角度) 字节对
+    SetPosition,
+    // 确认，序号与被确认的帧一致
+    Ack,
+    // 否认，负载第一个字节为原因码
+    Nack,
+}
+
+impl FrameKind {
+    pub fn as_u8(self) -> u8 {
+        match self {
+            FrameKind::Hello => 0x01,
+            FrameKind::Identify => 0x02,
+            FrameKind::Ping => 0x03,
+            FrameKind::SetPosition => 0x10,
+            FrameKind::Ack => 0x80,
+            FrameKind::Nack => 0x81,
+        }
+    }
+
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0x01 => Some(FrameKind::Hello),
+            0x02 => Some(FrameKind::Identify),
+            0x03 => Some(FrameKind::Ping),
+            0x10 => Some(FrameKind::SetPosition),
+            0x80 => Some(FrameKind::Ack),
+            0x81 => Some(FrameKind::Nack),
+            _ => None,
+        }
+    }
+}
+
+// 一个完整的协议帧
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub version: u8,
+    pub seq: u8,
+    pub kind: FrameKind,
+    pub payload: Vec<u8>,
+}
+
+impl Frame {
+    pub fn new(version: u8, seq: u8, kind: FrameKind, payload: Vec<u8>) -> Self {
+        Frame { version, seq, kind, payload }
+    }
+
+    // 将帧编码为待发送的字节，负载超过 MAX_PAYLOAD_LEN 时返回错误
+    pub fn encode(&self) -> Result<Vec<u8>, FrameError> {
+        if self.payload.len() > MAX_PAYLOAD_LEN {
+            return Err(FrameError::PayloadTooLong { seq: self.seq, len: self.payload.len() });
+        }
+
+        let mut bytes = Vec::with_capacity(HEADER_LEN + self.payload.len() + CRC_LEN);
+        bytes.push(FRAME_START);
+        bytes.push(self.version);
+        bytes.push(self.seq);
+        bytes.push(self.kind.as_u8());
+        bytes.push(self.payload.len() as u8);
+        bytes.extend_from_slice(&self.payload);
+
+        let crc = crc16(&bytes[1..]);
+        bytes.extend_from_slice(&crc.to_be_bytes());
+        Ok(bytes)
+    }
+}
+
+// 解码过程中发现的错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrameError {
+    // 校验失败，附带帧头中的序号
+    BadChecksum { seq: u8 },
+    // 未知的帧类型
+    UnknownKind { seq: u8, kind: u8 },
+    // 待编码的负载超过单帧上限
+    PayloadTooLong { seq: u8, len: usize },
+}
+
+impl std::fmt::Display for FrameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FrameError::BadChecksum { seq } => write!(f, "Checksum mismatch in frame {}", seq),
+            FrameError::UnknownKind { seq, kind } => write!(f, "Unknown frame kind 0x{:02X} in frame {}", kind, seq),
+            FrameError::PayloadTooLong { seq, len } => {
+                write!(f, "Payload of {} bytes in frame {} exceeds the {} byte limit", len, seq, MAX_PAYLOAD_LEN)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FrameError {}
+
+// 增量式帧解码器，负责从字节流中重新同步并切分出完整帧
+#[derive(Default)]
+pub struct FrameDecoder {
+    buffer: Vec<u8>,
+}
+
+impl FrameDecoder {
+    pub fn new() -> Self {
+        FrameDecoder { buffer: Vec::new() }
+    }
+
+    // 追加收到的原始字节
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    // 丢弃尚未解析的字节
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+    }
+
+    // 没有接收到一半的帧
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    // 尝试取出下一帧，数据不足时返回 None
+    pub fn next_frame(&mut self) -> Option<Result<Frame, FrameError>> {
+        loop {
+            // 丢弃起始字节之前的杂散数据(例如旧固件输出的文本)
+            match self.buffer.iter().position(|&b| b == FRAME_START) {
+                Some(start) => {
+                    self.buffer.drain(..start);
+                }
+                None => {
+                    self.buffer.clear();
+                    return None;
+                }
+            }
+
+            if self.buffer.len() < HEADER_LEN {
+                return None;
+            }
+
+            let payload_len = self.buffer[4] as usize;
+            if payload_len > MAX_PAYLOAD_LEN {
+                // 长度字段不可信，说明这不是真正的帧头
+                self.buffer.drain(..1);
+                continue;
+            }
+
+            let frame_len = HEADER_LEN + payload_len + CRC_LEN;
+            if self.buffer.len() < frame_len {
+                return None;
+            }
+
+            let frame_bytes: Vec<u8> = self.buffer.drain(..frame_len).collect();
+            let seq = frame_bytes[2];
+            let expected = crc16(&frame_bytes[1..HEADER_LEN + payload_len]);
+            let received = u16::from_be_bytes([frame_bytes[frame_len - 2], frame_bytes[frame_len - 1]]);
+            if expected != received {
+                // 把除起始字节外的内容放回缓冲区，以便从中重新同步
+                let rest = frame_bytes[1..].to_vec();
+                self.buffer.splice(..0, rest);
+                return Some(Err(FrameError::BadChecksum { seq }));
+            }
+
+            let kind = match FrameKind::from_u8(frame_bytes[3]) {
+                Some(kind) => kind,
+                None => return Some(Err(FrameError::UnknownKind { seq, kind: frame_bytes[3] })),
+            };
+
+            return Some(Ok(Frame {
+                version: frame_bytes[1],
+                seq,
+                kind,
+                payload: frame_bytes[HEADER_LEN..HEADER_LEN + payload_len].to_vec(),
+            }));
+        }
+    }
+}
+
+// 将 NACK 原因码转换为便于阅读的描述
+pub fn nack_reason(code: Option<u8>) -> &'static str {
+    match code {
+        Some(NACK_BAD_CHECKSUM) => "bad checksum",
+        Some(NACK_UNSUPPORTED) => "unsupported command",
+        Some(NACK_INVALID_PAYLOAD) => "invalid payload",
+        _ => "unknown reason",
+    }
+}
+
+// 设备对 Identify 的回复
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceIdentity {
+    pub protocol_version: u8,
+    pub firmware_version: String,
+    pub channel_count: u8,
+    // 固件报告的型号名称，可能为空
+    pub model: Option<String>,
+}
+
+// 解析 Identify 的 ACK 负载: [固件主版本][次版本][修订号][通道数][型号名称 UTF-8...]
+pub fn parse_identity(frame: &Frame) -> Option<DeviceIdentity> {
+    if frame.payload.len() < 4 {
+        return None;
+    }
+    let model = String::from_utf8_lossy(&frame.payload[4..]).trim().to_string();
+    Some(DeviceIdentity {
+        protocol_version: frame.version,
+        firmware_version: format!("{}.{}.{}", frame.payload[0], frame.payload[1], frame.payload[2]),
+        channel_count: frame.payload[3],
+        model: if model.is_empty() { None } else { Some(model) },
+    })
+}
+
+// CRC-16/CCITT-FALSE (多项式 0x1021，初始值 0xFFFF)
+pub fn crc16(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0xFFFF;
+    for &byte in data {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc_matches_ccitt_false_check_value() {
+        assert_eq!(crc16(b"123456789"), 0x29B1);
+    }
+
+    #[test]
+    fn encoded_frame_decodes_after_noise() {
+        let frame = Frame::new(PROTOCOL_VERSION, 7, FrameKind::SetPosition, vec![0, 90, 1, 45]);
+        let mut decoder = FrameDecoder::new();
+        // 旧版固件的文本输出混在帧之前
+        decoder.push(b"Position set to: 90,90\r\n");
+        decoder.push(&frame.encode().unwrap());
+
+        assert_eq!(decoder.next_frame(), Some(Ok(frame)));
+        assert_eq!(decoder.next_frame(), None);
+    }
+
+    #[test]
+    fn frame_split_across_reads_waits_for_the_rest() {
+        let bytes = Frame::new(PROTOCOL_VERSION, 1, FrameKind::Ping, Vec::new()).encode().unwrap();
+        let mut decoder = FrameDecoder::new();
+        decoder.push(&bytes[..3]);
+        assert_eq!(decoder.next_frame(), None);
+        decoder.push(&bytes[3..]);
+        assert!(matches!(decoder.next_frame(), Some(Ok(frame)) if frame.kind == FrameKind::Ping));
+    }
+
+    #[test]
+    fn corrupted_frame_reports_checksum_and_resynchronizes() {
+        let mut corrupted = Frame::new(PROTOCOL_VERSION, 3, FrameKind::Ping, Vec::new()).encode().unwrap();
+        let last = corrupted.len() - 1;
+        corrupted[last] ^= 0xFF;
+        let good = Frame::new(PROTOCOL_VERSION, 4, FrameKind::Identify, Vec::new());
+
+        let mut decoder = FrameDecoder::new();
+        decoder.push(&corrupted);
+        decoder.push(&good.encode().unwrap());
+        assert_eq!(decoder.next_frame(), Some(Err(FrameError::BadChecksum { seq: 3 })));
+        assert_eq!(decoder.next_frame(), Some(Ok(good)));
+    }
+
+    #[test]
+    fn unknown_kind_is_reported() {
+        let mut bytes = vec![FRAME_START, PROTOCOL_VERSION, 9, 0x7F, 0];
+        let crc = crc16(&bytes[1..]);
+        bytes.extend_from_slice(&crc.to_be_bytes());
+
+        let mut decoder = FrameDecoder::new();
+        decoder.push(&bytes);
+        assert_eq!(decoder.next_frame(), Some(Err(FrameError::UnknownKind { seq: 9, kind: 0x7F })));
+    }
+
+    #[test]
+    fn oversized_payload_is_rejected() {
+        let frame = Frame::new(PROTOCOL_VERSION, 0, FrameKind::SetPosition, vec![0; MAX_PAYLOAD_LEN + 1]);
+        assert_eq!(frame.encode(), Err(FrameError::PayloadTooLong { seq: 0, len: MAX_PAYLOAD_LEN + 1 }));
+        assert!(Frame::new(PROTOCOL_VERSION, 0, FrameKind::SetPosition, vec![0; MAX_PAYLOAD_LEN]).encode().is_ok());
+    }
+
+    #[test]
+    fn identity_payload_is_parsed() {
+        let mut payload = vec![2, 1, 0, 2];
+        payload.extend_from_slice(b"Desky R4");
+        let identity = parse_identity(&Frame::new(1, 0, FrameKind::Ack, payload)).unwrap();
+        assert_eq!(identity.firmware_version, "2.1.0");
+        assert_eq!(identity.channel_count, 2);
+        assert_eq!(identity.model.as_deref(), Some("Desky R4"));
+        assert_eq!(parse_identity(&Frame::new(1, 0, FrameKind::Ack, vec![1, 0])), None);
+    }
+}
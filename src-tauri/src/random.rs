@@ -0,0 +1,28 @@
+// 不依赖额外 crate 的随机数，用于重试抖动和 multipart boundary 等不要求密码学强度的场合
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+// 返回一个随机的 u64，RandomState 每次创建时使用不同的随机种子
+pub fn random_u64() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(0);
+    hasher.finish()
+}
+
+// 返回 [0, 1) 之间的随机数
+pub fn random_unit() -> f64 {
+    (random_u64() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn random_unit_stays_in_range() {
+        for _ in 0..1000 {
+            let value = random_unit();
+            assert!((0.0..1.0).contains(&value));
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use crate::events::{self, DEVICE_CONNECTION};
+use crate::random::random_unit;
+
+// 链路断开后的重连策略
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ReconnectPolicy {
+    pub enabled: bool,
+    // 第一次重试前的等待时间
+    pub initial_delay_ms: u64,
+    // 等待时间的上限
+    pub max_delay_ms: u64,
+    // 每次失败后等待时间的倍数
+    pub multiplier: f64,
+    // 随机抖动占等待时间的比例(0..=1)，避免多个设备同时重试
+    pub jitter: f64,
+    // 最大重试次数，为空时一直重试
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        ReconnectPolicy {
+            enabled: true,
+            initial_delay_ms: 250,
+            max_delay_ms: 10_000,
+            multiplier: 2.0,
+            jitter: 0.2,
+            max_attempts: None,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.multiplier.is_finite() || self.multiplier < 1.0 {
+            return Err("Reconnect multiplier must be at least 1".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.jitter) {
+            return Err("Reconnect jitter must be between 0 and 1".to_string());
+        }
+        if self.initial_delay_ms > self.max_delay_ms {
+            return Err("Reconnect initialDelayMs must not exceed maxDelayMs".to_string());
+        }
+        Ok(())
+    }
+
+    // 第 attempt 次重试(从 1 开始)前的等待时间，已包含抖动
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(32) as i32;
+        let base = (self.initial_delay_ms as f64 * self.multiplier.powi(exponent)).min(self.max_delay_ms as f64);
+        let jitter = base * self.jitter * (random_unit() * 2.0 - 1.0);
+        Duration::from_millis((base + jitter).max(0.0) as u64)
+    }
+}
+
+// 设备连接状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConnectionState {
+    Connected,
+    Disconnected,
+    Reconnecting,
+    // 重试次数用尽，需要手动重新连接
+    Failed,
+}
+
+// device-connection 事件的负载
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionEvent {
+    pub device_name: String,
+    pub state: ConnectionState,
+    // 当前的重试次数，非重连状态时为 0
+    pub attempt: u32,
+    // 距离下一次重试的时间
+    pub retry_in_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+impl ConnectionEvent {
+    pub fn new(device_name: &str, state: ConnectionState) -> Self {
+        ConnectionEvent {
+            device_name: device_name.to_string(),
+            state,
+            attempt: 0,
+            retry_in_ms: None,
+            error: None,
+        }
+    }
+
+    pub fn emit(self) {
+        events::emit(DEVICE_CONNECTION, self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delay_grows_by_the_multiplier_up_to_the_limit() {
+        let policy = ReconnectPolicy { jitter: 0.0, ..ReconnectPolicy::default() };
+        assert_eq!(policy.delay(1), Duration::from_millis(250));
+        assert_eq!(policy.delay(2), Duration::from_millis(500));
+        assert_eq!(policy.delay(4), Duration::from_millis(2000));
+        assert_eq!(policy.delay(10), Duration::from_millis(10_000));
+        assert_eq!(policy.delay(u32::MAX), Duration::from_millis(10_000));
+    }
+
+    #[test]
+    fn jitter_stays_within_its_fraction() {
+        let policy = ReconnectPolicy::default();
+        for _ in 0..100 {
+            let ms = policy.delay(3).as_millis();
+            assert!((800..=1200).contains(&ms), "{}", ms);
+        }
+    }
+
+    #[test]
+    fn invalid_policies_are_rejected() {
+        assert!(ReconnectPolicy { multiplier: 0.5, ..ReconnectPolicy::default() }.validate().is_err());
+        assert!(ReconnectPolicy { multiplier: f64::NAN, ..ReconnectPolicy::default() }.validate().is_err());
+        assert!(ReconnectPolicy { jitter: 1.5, ..ReconnectPolicy::default() }.validate().is_err());
+        assert!(ReconnectPolicy { initial_delay_ms: 20_000, ..ReconnectPolicy::default() }.validate().is_err());
+        assert!(ReconnectPolicy::default().validate().is_ok());
+    }
+}
@@ -0,0 +1,187 @@
+use std::time::Duration;
+use reqwest::{Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use crate::random::random_unit;
+
+// 退避时间的随机抖动比例，避免多个请求同时重试
+const BACKOFF_JITTER: f64 = 0.2;
+
+// 代理请求的超时和重试策略，超时时间为 0 表示不限制
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RequestPolicy {
+    // 建立连接的超时时间
+    pub connect_timeout_ms: u64,
+    // 等待响应头或下一段响应体的最长时间
+    pub read_timeout_ms: u64,
+    // 包括所有重试在内的总时间，流式请求只限制到收到响应头为止
+    pub total_timeout_ms: u64,
+    // 第一次请求之外的最大重试次数
+    pub max_retries: u32,
+    // 第一次重试前的等待时间，之后每次翻倍
+    pub initial_backoff_ms: u64,
+    // 等待时间的上限，服务器要求的 Retry-After 超过该值时不再重试
+    pub max_backoff_ms: u64,
+    // 是否允许重试 POST、PATCH 等非幂等请求
+    pub retry_non_idempotent: bool,
+    // 视为暂时性错误、需要重试的状态码
+    pub retry_statuses: Vec<u16>,
+}
+
+impl Default for RequestPolicy {
+    fn default() -> Self {
+        RequestPolicy {
+            connect_timeout_ms: 10_000,
+            read_timeout_ms: 30_000,
+            total_timeout_ms: 120_000,
+            max_retries: 2,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 10_000,
+            retry_non_idempotent: false,
+            retry_statuses: vec![408, 429, 502, 503, 504],
+        }
+    }
+}
+
+impl RequestPolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.initial_backoff_ms > self.max_backoff_ms {
+            return Err("Request initialBackoffMs must not exceed maxBackoffMs".to_string());
+        }
+        if let Some(status) = self.retry_statuses.iter().find(|&&status| StatusCode::from_u16(status).is_err()) {
+            return Err(format!("Invalid retry status code: {}", status));
+        }
+        Ok(())
+    }
+
+    pub fn connect_timeout(&self) -> Option<Duration> {
+        limit(self.connect_timeout_ms)
+    }
+
+    pub fn read_timeout(&self) -> Option<Duration> {
+        limit(self.read_timeout_ms)
+    }
+
+    pub fn total_timeout(&self) -> Option<Duration> {
+        limit(self.total_timeout_ms)
+    }
+
+    // 该方法的请求最多发送的次数，非幂等请求默认只发送一次
+    pub fn max_attempts(&self, method: &Method) -> u32 {
+        if self.retry_non_idempotent || is_idempotent(method) {
+            self.max_retries.saturating_add(1)
+        } else {
+            1
+        }
+    }
+
+    pub fn should_retry_status(&self, status: StatusCode) -> bool {
+        self.retry_statuses.contains(&status.as_u16())
+    }
+
+    pub fn max_backoff(&self) -> Duration {
+        Duration::from_millis(self.max_backoff_ms)
+    }
+
+    // 第 attempt 次请求(从 1 开始)失败后的等待时间，已包含抖动
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(32) as i32;
+        let base = (self.initial_backoff_ms as f64 * 2f64.powi(exponent)).min(self.max_backoff_ms as f64);
+        let jitter = base * BACKOFF_JITTER * (random_unit() * 2.0 - 1.0);
+        Duration::from_millis((base + jitter).max(0.0) as u64)
+    }
+}
+
+// 单个请求对默认策略的覆盖，未指定的字段沿用默认策略
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestPolicyOverride {
+    pub connect_timeout_ms: Option<u64>,
+    pub read_timeout_ms: Option<u64>,
+    pub total_timeout_ms: Option<u64>,
+    pub max_retries: Option<u32>,
+    pub initial_backoff_ms: Option<u64>,
+    pub max_backoff_ms: Option<u64>,
+    pub retry_non_idempotent: Option<bool>,
+    pub retry_statuses: Option<Vec<u16>>,
+}
+
+impl RequestPolicyOverride {
+    pub fn apply(self, base: &RequestPolicy) -> Result<RequestPolicy, String> {
+        let policy = RequestPolicy {
+            connect_timeout_ms: self.connect_timeout_ms.unwrap_or(base.connect_timeout_ms),
+            read_timeout_ms: self.read_timeout_ms.unwrap_or(base.read_timeout_ms),
+            total_timeout_ms: self.total_timeout_ms.unwrap_or(base.total_timeout_ms),
+            max_retries: self.max_retries.unwrap_or(base.max_retries),
+            initial_backoff_ms: self.initial_backoff_ms.unwrap_or(base.initial_backoff_ms),
+            max_backoff_ms: self.max_backoff_ms.unwrap_or(base.max_backoff_ms),
+            retry_non_idempotent: self.retry_non_idempotent.unwrap_or(base.retry_non_idempotent),
+            retry_statuses: self.retry_statuses.unwrap_or_else(|| base.retry_statuses.clone()),
+        };
+        policy.validate()?;
+        Ok(policy)
+    }
+}
+
+fn limit(ms: u64) -> Option<Duration> {
+    (ms > 0).then(|| Duration::from_millis(ms))
+}
+
+// 重复发送不会产生额外副作用的方法
+fn is_idempotent(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS | Method::PUT | Method::DELETE)
+}
+
+// 解析 Retry-After 响应头，支持秒数和 HTTP 日期两种格式
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    // 日期已经过去时立即重试
+    Some((date.with_timezone(&chrono::Utc) - chrono::Utc::now()).to_std().unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_the_limit_with_jitter() {
+        let policy = RequestPolicy::default();
+        let within = |delay: Duration, base: f64| {
+            let ms = delay.as_millis() as f64;
+            ms >= base * (1.0 - BACKOFF_JITTER) - 1.0 && ms <= base * (1.0 + BACKOFF_JITTER)
+        };
+        for _ in 0..100 {
+            assert!(within(policy.backoff(1), 500.0));
+            assert!(within(policy.backoff(3), 2000.0));
+            assert!(within(policy.backoff(10), 10_000.0));
+            assert!(within(policy.backoff(u32::MAX), 10_000.0));
+        }
+    }
+
+    #[test]
+    fn retry_after_accepts_seconds() {
+        assert_eq!(parse_retry_after("120"), Some(Duration::from_secs(120)));
+        assert_eq!(parse_retry_after(" 0 "), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("-1"), None);
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+
+    #[test]
+    fn retry_after_accepts_http_dates() {
+        let future = (chrono::Utc::now() + chrono::Duration::seconds(30)).to_rfc2822();
+        let delay = parse_retry_after(&future).unwrap();
+        assert!(delay > Duration::from_secs(25) && delay <= Duration::from_secs(30));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn non_idempotent_requests_are_sent_once_by_default() {
+        let policy = RequestPolicy::default();
+        assert_eq!(policy.max_attempts(&Method::GET), 3);
+        assert_eq!(policy.max_attempts(&Method::POST), 1);
+    }
+}
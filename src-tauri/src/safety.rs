@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use crate::app_data::JsonFile;
+use crate::commands::log_message;
+
+// 定义模块名称常量
+const MODEL_NAME: &str = "safety";
+// 安全区域配置在应用数据目录中的文件名
+pub const SAFETY_FILE: &str = "safety.json";
+
+// 限幅后落入另一个禁区时最多重新调整的次数
+const MAX_CLAMP_PASSES: usize = 4;
+
+// 目标落入禁区时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RegionAction {
+    // 把目标推到禁区最近的边界上
+    Clamp,
+    // 直接拒绝该目标
+    Reject,
+}
+
+// X/Y 组合空间中的矩形禁区，坐标为校准前的逻辑角度
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForbiddenRegion {
+    pub name: String,
+    pub x_min: f64,
+    pub x_max: f64,
+    pub y_min: f64,
+    pub y_max: f64,
+    pub action: RegionAction,
+}
+
+impl ForbiddenRegion {
+    fn validate(&self) -> Result<(), String> {
+        let values = [self.x_min, self.x_max, self.y_min, self.y_max];
+        if values.iter().any(|v| !v.is_finite()) || self.x_min > self.x_max || self.y_min > self.y_max {
+            return Err(format!("Invalid bounds for forbidden region {}", self.name));
+        }
+        Ok(())
+    }
+
+    // 边界本身视为允许的位置，这样限幅后的点不会再次落入同一禁区
+    fn contains(&self, x: f64, y: f64) -> bool {
+        x > self.x_min && x < self.x_max && y > self.y_min && y < self.y_max
+    }
+
+    // 将点移动到距离最近的边界上
+    fn push_out(&self, x: f64, y: f64) -> (f64, f64) {
+        let candidates = [
+            (x - self.x_min, (self.x_min, y)),
+            (self.x_max - x, (self.x_max, y)),
+            (y - self.y_min, (x, self.y_min)),
+            (self.y_max - y, (x, self.y_max)),
+        ];
+        candidates.iter()
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, point)| *point)
+            .unwrap_or((x, y))
+    }
+}
+
+// 单个设备的安全包络
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SafetyEnvelope {
+    pub regions: Vec<ForbiddenRegion>,
+}
+
+impl SafetyEnvelope {
+    pub fn validate(&self) -> Result<(), String> {
+        self.regions.iter().try_for_each(ForbiddenRegion::validate)
+    }
+
+    // 检查 X/Y 目标，落入禁区时按禁区设置限幅或拒绝
+    pub fn check(&self, x: f64, y: f64) -> Result<(f64, f64), String> {
+        let (mut x, mut y) = (x, y);
+        for _ in 0..MAX_CLAMP_PASSES {
+            let region = match self.regions.iter().find(|region| region.contains(x, y)) {
+                Some(region) => region,
+                None => return Ok((x, y)),
+            };
+
+            match region.action {
+                RegionAction::Reject => {
+                    return Err(format!("Target ({:.1}, {:.1}) is inside forbidden region {}", x, y, region.name));
+                }
+                RegionAction::Clamp => {
+                    (x, y) = region.push_out(x, y);
+                }
+            }
+        }
+
+        match self.regions.iter().find(|region| region.contains(x, y)) {
+            Some(region) => Err(format!("Target ({:.1}, {:.1}) cannot be moved out of forbidden region {}", x, y, region.name)),
+            None => Ok((x, y)),
+        }
+    }
+}
+
+// 所有设备的安全包络，并持久化到应用数据目录
+#[derive(Default)]
+pub struct SafetyStore {
+    devices: JsonFile<HashMap<String, SafetyEnvelope>>,
+}
+
+impl SafetyStore {
+    // 从文件加载安全包络，其中有边界无效的禁区时拒绝加载，不会以不完整的包络继续运行
+    pub fn load(path: PathBuf) -> Result<Self, String> {
+        let devices: JsonFile<HashMap<String, SafetyEnvelope>> = JsonFile::load(path.clone())?;
+        for (device_name, envelope) in devices.data() {
+            envelope.validate()
+                .map_err(|e| format!("Invalid safety envelope for {} in {}: {}", device_name, path.display(), e))?;
+        }
+
+        log_message(
+            format!("Loaded safety envelopes for {} device(s) from {}", devices.data().len(), path.display()),
+            "INFO".to_string(),
+            MODEL_NAME.to_string(),
+        );
+
+        Ok(SafetyStore { devices })
+    }
+
+    pub fn envelope(&self, device_name: &str) -> SafetyEnvelope {
+        self.devices.data().get(device_name).cloned().unwrap_or_default()
+    }
+
+    pub fn set_envelope(&mut self, device_name: &str, envelope: SafetyEnvelope) -> Result<(), String> {
+        envelope.validate()?;
+        self.devices.update("safety envelopes", |devices| {
+            devices.insert(device_name.to_string(), envelope);
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn region(name: &str, bounds: [f64; 4], action: RegionAction) -> ForbiddenRegion {
+        ForbiddenRegion {
+            name: name.to_string(),
+            x_min: bounds[0],
+            x_max: bounds[1],
+            y_min: bounds[2],
+            y_max: bounds[3],
+            action,
+        }
+    }
+
+    #[test]
+    fn targets_outside_regions_pass_unchanged() {
+        let envelope = SafetyEnvelope {
+            regions: vec![region("desk", [40.0, 80.0, 0.0, 30.0], RegionAction::Reject)],
+        };
+        assert_eq!(envelope.check(90.0, 10.0), Ok((90.0, 10.0)));
+        assert_eq!(envelope.check(40.0, 10.0), Ok((40.0, 10.0)));
+    }
+
+    #[test]
+    fn clamp_moves_the_target_to_the_nearest_edge() {
+        let envelope = SafetyEnvelope {
+            regions: vec![region("desk", [40.0, 80.0, 0.0, 30.0], RegionAction::Clamp)],
+        };
+        assert_eq!(envelope.check(45.0, 20.0), Ok((40.0, 20.0)));
+        assert_eq!(envelope.check(60.0, 28.0), Ok((60.0, 30.0)));
+    }
+
+    #[test]
+    fn reject_refuses_the_target() {
+        let envelope = SafetyEnvelope {
+            regions: vec![region("monitor", [40.0, 80.0, 0.0, 30.0], RegionAction::Reject)],
+        };
+        assert!(envelope.check(60.0, 10.0).unwrap_err().contains("monitor"));
+    }
+
+    #[test]
+    fn invalid_regions_are_rejected() {
+        let inverted = SafetyEnvelope {
+            regions: vec![region("inverted", [80.0, 40.0, 0.0, 30.0], RegionAction::Clamp)],
+        };
+        assert!(inverted.validate().is_err());
+        let nan = SafetyEnvelope {
+            regions: vec![region("nan", [f64::NAN, 40.0, 0.0, 30.0], RegionAction::Clamp)],
+        };
+        assert!(nan.validate().is_err());
+    }
+
+    #[test]
+    fn invalid_safety_file_is_not_loaded() {
+        let dir = std::env::temp_dir().join(format!("desky-safety-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(SAFETY_FILE);
+
+        std::fs::write(
+            &path,
+            r#"{"board": {"regions": [{"name": "desk", "xMin": 80, "xMax": 40, "yMin": 0, "yMax": 30, "action": "clamp"}]}}"#,
+        ).unwrap();
+        assert!(SafetyStore::load(path.clone()).err().unwrap().contains("desk"));
+
+        std::fs::write(
+            &path,
+            r#"{"board": {"regions": [{"name": "desk", "xMin": 40, "xMax": 80, "yMin": 0, "yMax": 30, "action": "clamp"}]}}"#,
+        ).unwrap();
+        assert_eq!(SafetyStore::load(path.clone()).unwrap().envelope("board").regions.len(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
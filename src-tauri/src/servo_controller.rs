@@ -1,44 +1,306 @@
-use serialport::SerialPort;
-use std::time::Duration;
-use std::io::{Write, Read};
+use std::time::{Duration, Instant};
+use std::io::{self, ErrorKind};
+use serde::Serialize;
 use crate::commands::log_message;
+use crate::device_manager::DeviceConfig;
+use crate::protocol::{nack_reason, parse_identity, DeviceIdentity, Frame, FrameDecoder, FrameKind, MAX_PAYLOAD_LEN, PROTOCOL_VERSION};
+use crate::transport::{open_transport, Transport};
+
+// 版本协商时每次等待回复的时间及尝试次数
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_millis(300);
+const HANDSHAKE_ATTEMPTS: usize = 3;
+// 等待 Ping 回复的时间
+const PING_TIMEOUT: Duration = Duration::from_millis(500);
+// 等待 ACK/NACK 的时间及最大重传次数
+const ACK_TIMEOUT: Duration = Duration::from_millis(200);
+const MAX_RETRANSMISSIONS: usize = 3;
+// 旧版固件只支持 X/Y 两个通道，上电后位于中间位置
+const LEGACY_CHANNELS: usize = 2;
+const LEGACY_CENTER: u8 = 90;
+
+// 旧版固件确认位置时输出的前缀
+const LEGACY_ACK_PREFIX: &str = "Position set to:";
+
+// 设备对位置命令的确认结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AckStatus {
+    // 设备确认的角度与下发的一致
+    Acked,
+    // 设备确认了命令，但实际角度被固件限幅
+    Clamped,
+    // 没有收到可识别的确认
+    TimedOut,
+}
+
+// 一次位置命令的确认信息，角度均以 (通道序号, 角度) 表示
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PositionAck {
+    pub status: AckStatus,
+    pub commanded: Vec<(u8, u8)>,
+    // 设备报告的实际角度，未收到确认时为空
+    pub reported: Vec<(u8, u8)>,
+}
+
+impl PositionAck {
+    // 比较下发和设备报告的角度，得出确认状态
+    fn compare(commanded: Vec<(u8, u8)>, reported: Option<Vec<(u8, u8)>>) -> Self {
+        let reported = match reported {
+            Some(reported) => reported,
+            None => {
+                return PositionAck {
+                    status: AckStatus::TimedOut,
+                    commanded,
+                    reported: Vec::new(),
+                };
+            }
+        };
+
+        let matches = commanded.iter().all(|entry| reported.contains(entry));
+        PositionAck {
+            status: if matches { AckStatus::Acked } else { AckStatus::Clamped },
+            commanded,
+            reported,
+        }
+    }
+}
+
+// 存活探测的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PingResult {
+    // 收到回复，附带往返时间
+    Pong(Duration),
+    // 超时未收到回复
+    NoReply,
+    // 旧版固件不支持 Ping，只确认了端口仍然可用
+    PortOpen,
+}
+
+// 身份查询的结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum Identification {
+    // 旧版文本协议的固件，无法查询版本和通道数
+    Legacy,
+    // 支持帧协议的固件，没有回复身份查询时为 None
+    Framed(Option<DeviceIdentity>),
+}
+
+// 与设备之间使用的线路协议
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WireProtocol {
+    // 旧版固件使用的 "x,y\n" 文本格式
+    Legacy,
+    // 带序号、校验和确认的帧格式
+    Framed { version: u8 },
+}
 
 pub struct ServoController {
-    port: Box<dyn SerialPort>,
+    port: Box<dyn Transport>,
+    protocol: WireProtocol,
+    next_seq: u8,
+    decoder: FrameDecoder,
+    read_timeout: Duration,
+    line_terminator: String,
+    // 每个通道最近一次发送的角度
+    last_sent: Vec<Option<u8>>,
 }
 
 impl ServoController {
-    pub fn new(port_name: &str) -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn new(port_name: &str, config: &DeviceConfig) -> Result<Self, Box<dyn std::error::Error>> {
         log_message(
             format!("Attempting to create new ServoController for port {}", port_name),
             "INFO".to_string(),
             "servo_controller".to_string(),
         );
 
-        let port = serialport::new(port_name, 9600)
-            .timeout(Duration::from_millis(1000))
-            .open()?;
+        let port = open_transport(port_name, config)?;
+
+        Self::with_transport(port, config)
+    }
 
+    // 使用已经打开的传输通道创建控制器，例如 TCP 或内存回环
+    pub fn with_transport(port: Box<dyn Transport>, config: &DeviceConfig) -> Result<Self, Box<dyn std::error::Error>> {
         log_message(
-            format!("Successfully opened serial port {}", port_name),
+            format!("ServoController attached to {}", port.description()),
             "INFO".to_string(),
             "servo_controller".to_string(),
         );
 
-        Ok(ServoController { port })
+        let mut controller = ServoController {
+            port,
+            protocol: WireProtocol::Legacy,
+            next_seq: 0,
+            decoder: FrameDecoder::new(),
+            read_timeout: Duration::from_millis(config.read_timeout_ms),
+            line_terminator: config.line_terminator.clone(),
+            last_sent: Vec::new(),
+        };
+        controller.protocol = controller.negotiate()?;
+        Ok(controller)
     }
 
-    pub fn set_position(&mut self, x: Option<u8>, y: Option<u8>) -> Result<(), Box<dyn std::error::Error>> {
-        let x_value = x.unwrap_or(90);
-        let y_value = y.unwrap_or(90);
-        
+    // 连接时协商协议版本，设备没有按帧格式回复时回退到旧版文本协议
+    fn negotiate(&mut self) -> Result<WireProtocol, Box<dyn std::error::Error>> {
+        for attempt in 1..=HANDSHAKE_ATTEMPTS {
+            let seq = self.take_seq();
+            let hello = Frame::new(PROTOCOL_VERSION, seq, FrameKind::Hello, vec![PROTOCOL_VERSION]);
+            let mut bytes = hello.encode()?;
+            // 追加换行，让旧版固件把这一行当作无效输入丢弃
+            bytes.extend_from_slice(self.line_terminator.as_bytes());
+            self.port.write_all(&bytes)?;
+            self.port.flush()?;
+
+            if let Some(reply) = self.wait_for_reply(seq, HANDSHAKE_TIMEOUT)? {
+                if reply.kind == FrameKind::Ack {
+                    let version = reply.payload.first().copied().unwrap_or(reply.version).min(PROTOCOL_VERSION);
+                    log_message(
+                        format!("Negotiated framed protocol v{} with {}", version, self.port.description()),
+                        "INFO".to_string(),
+                        "servo_controller".to_string(),
+                    );
+                    return Ok(WireProtocol::Framed { version });
+                }
+            }
+
+            log_message(
+                format!("No framed handshake reply (attempt {}/{})", attempt, HANDSHAKE_ATTEMPTS),
+                "WARN".to_string(),
+                "servo_controller".to_string(),
+            );
+        }
+
         log_message(
-            format!("Setting servo position: X={}, Y={}", x_value, y_value),
+            format!("Falling back to legacy text protocol for {}", self.port.description()),
+            "WARN".to_string(),
+            "servo_controller".to_string(),
+        );
+        self.decoder.clear();
+        self.port.set_read_timeout(self.read_timeout)?;
+        Ok(WireProtocol::Legacy)
+    }
+
+    // 查询设备身份，旧版文本协议的固件无法查询，只报告所用的协议
+    pub fn identify(&mut self) -> Result<Identification, Box<dyn std::error::Error>> {
+        if self.protocol == WireProtocol::Legacy {
+            return Ok(Identification::Legacy);
+        }
+
+        let identity = self.send_with_retransmit(FrameKind::Identify, Vec::new())?
+            .and_then(|reply| parse_identity(&reply));
+        log_message(
+            format!("Identify reply from {}: {:?}", self.port.description(), identity),
             "INFO".to_string(),
             "servo_controller".to_string(),
         );
+        Ok(Identification::Framed(identity))
+    }
+
+    // 发送 Ping 并测量往返时间，不会移动舵机
+    pub fn ping(&mut self) -> Result<PingResult, Box<dyn std::error::Error>> {
+        let version = match self.protocol {
+            WireProtocol::Framed { version } => version,
+            WireProtocol::Legacy => {
+                self.port.check_open()?;
+                return Ok(PingResult::PortOpen);
+            }
+        };
+
+        let seq = self.take_seq();
+        let frame = Frame::new(version, seq, FrameKind::Ping, Vec::new()).encode()?;
+        let started = Instant::now();
+        self.port.write_all(&frame)?;
+        self.port.flush()?;
+
+        let result = match self.wait_for_reply(seq, PING_TIMEOUT)? {
+            Some(reply) if reply.kind == FrameKind::Ack => PingResult::Pong(started.elapsed()),
+            _ => PingResult::NoReply,
+        };
+        log_message(
+            format!("Ping {} on {}: {:?}", seq, self.port.description(), result),
+            "INFO".to_string(),
+            "servo_controller".to_string(),
+        );
+        Ok(result)
+    }
+
+    // 设置若干通道的角度，只发送给定的通道，并返回设备的确认结果
+    pub fn set_channels(&mut self, channels: &[(u8, u8)]) -> Result<PositionAck, Box<dyn std::error::Error>> {
+
+        log_message(
+            format!("Setting servo channels: {:?}", channels),
+            "INFO".to_string(),
+            "servo_controller".to_string(),
+        );
+
+        let ack = match self.protocol {
+            WireProtocol::Legacy => self.set_channels_legacy(channels)?,
+            WireProtocol::Framed { .. } => {
+                // 通道较多时拆成多帧发送，任意一帧没有得到确认都视为整条命令未确认
+                let mut reported = Some(Vec::with_capacity(channels.len()));
+                for batch in channels.chunks(MAX_PAYLOAD_LEN / 2) {
+                    let payload = batch.iter().flat_map(|&(channel, angle)| [channel, angle]).collect();
+                    let reply = self.send_with_retransmit(FrameKind::SetPosition, payload)?;
+                    // ACK 负载为设备实际应用的 (通道, 角度) 字节对，负载为空表示按命令执行
+                    match (reply, reported.as_mut()) {
+                        (Some(frame), Some(reported)) if frame.payload.is_empty() => reported.extend_from_slice(batch),
+                        (Some(frame), Some(reported)) => {
+                            reported.extend(frame.payload.chunks_exact(2).map(|pair| (pair[0], pair[1])));
+                        }
+                        _ => reported = None,
+                    }
+                }
+                PositionAck::compare(channels.to_vec(), reported)
+            }
+        };
 
-        let command = format!("{},{}\n", x_value, y_value);
+        if ack.status != AckStatus::Acked {
+            log_message(
+                format!("Position command {:?}: commanded {:?}, reported {:?}", ack.status, ack.commanded, ack.reported),
+                "WARN".to_string(),
+                "servo_controller".to_string(),
+            );
+        }
+
+        for &(channel, angle) in channels {
+            let index = channel as usize;
+            if self.last_sent.len() <= index {
+                self.last_sent.resize(index + 1, None);
+            }
+            self.last_sent[index] = Some(angle);
+        }
+        Ok(ack)
+    }
+
+    // 旧版固件每次都需要完整的 "x,y"，未指定的轴沿用上一次发送的值
+    fn set_channels_legacy(&mut self, channels: &[(u8, u8)]) -> Result<PositionAck, Box<dyn std::error::Error>> {
+        let mut values = [LEGACY_CENTER; LEGACY_CHANNELS];
+        for (index, value) in values.iter_mut().enumerate() {
+            if let Some(Some(last)) = self.last_sent.get(index) {
+                *value = *last;
+            }
+        }
+        for &(channel, angle) in channels {
+            let slot = values.get_mut(channel as usize)
+                .ok_or_else(|| format!("Legacy firmware does not support channel {}", channel))?;
+            *slot = angle;
+        }
+        let response = self.set_position_legacy(values[0], values[1])?;
+        let commanded = values.iter().enumerate().map(|(index, &angle)| (index as u8, angle)).collect();
+        let reported = response.as_deref().and_then(parse_legacy_ack);
+        if response.is_some() && reported.is_none() {
+            log_message(
+                format!("Unrecognized response from Arduino: {:?}", response),
+                "WARN".to_string(),
+                "servo_controller".to_string(),
+            );
+        }
+        Ok(PositionAck::compare(commanded, reported))
+    }
+
+    // 发送旧版文本命令，返回 Arduino 回复的一行文本
+    fn set_position_legacy(&mut self, x_value: u8, y_value: u8) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let command = format!("{},{}{}", x_value, y_value, self.line_terminator);
         self.port.write_all(command.as_bytes())?;
         self.port.flush()?;
 
@@ -48,38 +310,169 @@ impl ServoController {
             "servo_controller".to_string(),
         );
 
-        // 等待一段时间，让 Arduino 有时间处理命令
-        std::thread::sleep(Duration::from_millis(100));
-
-        // 读取 Arduino 的响应
-        let mut response = String::new();
-        let mut serial_buf: Vec<u8> = vec![0; 1000];
-        match self.port.read(serial_buf.as_mut_slice()) {
-            Ok(t) => {
-                response.push_str(&String::from_utf8_lossy(&serial_buf[..t]));
+        // 读取 Arduino 的响应，直到收到完整的一行或超时
+        let response = self.read_line()?;
+        match &response {
+            Some(response) => {
                 log_message(
                     format!("Received response from Arduino: {}", response.trim()),
                     "INFO".to_string(),
                     "servo_controller".to_string(),
                 );
             },
-            Err(ref e) if e.kind() == std::io::ErrorKind::TimedOut => {
+            None => {
                 log_message(
                     "No response from Arduino (timeout)".to_string(),
                     "WARN".to_string(),
                     "servo_controller".to_string(),
                 );
             },
-            Err(e) => {
-                log_message(
-                    format!("Error reading from serial port: {}", e),
-                    "ERROR".to_string(),
-                    "servo_controller".to_string(),
-                );
-                return Err(Box::new(e));
+        }
+
+        Ok(response)
+    }
+
+    // 发送一帧并等待确认，超时或收到 NACK 时重传，重传用尽仍未确认时返回 None
+    fn send_with_retransmit(&mut self, kind: FrameKind, payload: Vec<u8>) -> Result<Option<Frame>, Box<dyn std::error::Error>> {
+        let version = match self.protocol {
+            WireProtocol::Framed { version } => version,
+            WireProtocol::Legacy => return Err("Framed command sent on a legacy connection".into()),
+        };
+        let seq = self.take_seq();
+        let frame = Frame::new(version, seq, kind, payload).encode()?;
+
+        for attempt in 1..=MAX_RETRANSMISSIONS + 1 {
+            self.port.write_all(&frame)?;
+            self.port.flush()?;
+
+            match self.wait_for_reply(seq, ACK_TIMEOUT)? {
+                Some(reply) if reply.kind == FrameKind::Ack => {
+                    log_message(
+                        format!("Frame {} acknowledged (attempt {})", seq, attempt),
+                        "INFO".to_string(),
+                        "servo_controller".to_string(),
+                    );
+                    return Ok(Some(reply));
+                }
+                Some(reply) => {
+                    log_message(
+                        format!("Frame {} rejected with NACK: {} (attempt {})", seq, nack_reason(reply.payload.first().copied()), attempt),
+                        "WARN".to_string(),
+                        "servo_controller".to_string(),
+                    );
+                }
+                None => {
+                    log_message(
+                        format!("Timed out waiting for ACK of frame {} (attempt {})", seq, attempt),
+                        "WARN".to_string(),
+                        "servo_controller".to_string(),
+                    );
+                }
+            }
+        }
+
+        log_message(
+            format!("Frame {} not acknowledged after {} retransmissions", seq, MAX_RETRANSMISSIONS),
+            "ERROR".to_string(),
+            "servo_controller".to_string(),
+        );
+        Ok(None)
+    }
+
+    // 等待与指定序号匹配的 ACK 或 NACK，超时返回 None
+    fn wait_for_reply(&mut self, seq: u8, timeout: Duration) -> Result<Option<Frame>, Box<dyn std::error::Error>> {
+        let deadline = Instant::now() + timeout;
+        let mut buf = [0u8; 256];
+
+        loop {
+            while let Some(result) = self.decoder.next_frame() {
+                match result {
+                    Ok(frame) if frame.seq == seq && matches!(frame.kind, FrameKind::Ack | FrameKind::Nack) => {
+                        return Ok(Some(frame));
+                    }
+                    Ok(frame) => {
+                        log_message(
+                            format!("Ignoring unexpected frame {:?} #{}", frame.kind, frame.seq),
+                            "WARN".to_string(),
+                            "servo_controller".to_string(),
+                        );
+                    }
+                    Err(e) => {
+                        log_message(
+                            format!("Discarding corrupted frame: {}", e),
+                            "WARN".to_string(),
+                            "servo_controller".to_string(),
+                        );
+                    }
+                }
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(None);
+            }
+
+            self.port.set_read_timeout(deadline - now)?;
+            match self.port.read(&mut buf) {
+                Ok(0) => return Err(Box::new(connection_closed())),
+                Ok(n) => self.decoder.push(&buf[..n]),
+                Err(ref e) if e.kind() == ErrorKind::TimedOut => return Ok(None),
+                Err(e) => return Err(Box::new(e)),
             }
         }
+    }
+
+    // 读取一行文本响应，超时前没有收到完整的一行时返回已收到的部分或 None
+    fn read_line(&mut self) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let deadline = Instant::now() + self.read_timeout;
+        let mut line: Vec<u8> = Vec::new();
+        let mut byte = [0u8; 1];
 
-        Ok(())
+        loop {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+
+            self.port.set_read_timeout(deadline - now)?;
+            match self.port.read(&mut byte) {
+                Ok(0) => return Err(Box::new(connection_closed())),
+                Ok(_) if byte[0] == b'\n' => break,
+                Ok(_) => line.push(byte[0]),
+                Err(ref e) if e.kind() == ErrorKind::TimedOut => break,
+                Err(e) => {
+                    log_message(
+                        format!("Error reading from serial port: {}", e),
+                        "ERROR".to_string(),
+                        "servo_controller".to_string(),
+                    );
+                    return Err(Box::new(e));
+                }
+            }
+        }
+
+        if line.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(String::from_utf8_lossy(&line).into_owned()))
+        }
+    }
+
+    fn take_seq(&mut self) -> u8 {
+        let seq = self.next_seq;
+        self.next_seq = self.next_seq.wrapping_add(1);
+        seq
     }
-}
\ No newline at end of file
+}
+
+// 解析旧版固件的确认行，例如 "Position set to: 90,45"
+fn parse_legacy_ack(line: &str) -> Option<Vec<(u8, u8)>> {
+    let values = line.trim().strip_prefix(LEGACY_ACK_PREFIX)?;
+    let (x, y) = values.split_once(',')?;
+    Some(vec![(0, x.trim().parse().ok()?), (1, y.trim().parse().ok()?)])
+}
+
+// 对端关闭连接，按 I/O 错误处理，工作线程据此判断链路已断开
+fn connection_closed() -> io::Error {
+    io::Error::new(ErrorKind::UnexpectedEof, "Connection closed by device")
+}
@@ -0,0 +1,230 @@
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use tokio::sync::{mpsc, oneshot};
+use crate::commands::log_message;
+use crate::device_manager::DeviceConfig;
+use crate::servo_controller::{Identification, PingResult, PositionAck, ServoController};
+
+// 定义模块名称常量
+const MODEL_NAME: &str = "servo_worker";
+
+// 发送给 I/O 线程的请求，结果通过 oneshot 通道返回
+enum ServoRequest {
+    SetChannels {
+        channels: Vec<(u8, u8)>,
+        reply: oneshot::Sender<Result<PositionAck, String>>,
+    },
+    Identify {
+        reply: oneshot::Sender<Result<Identification, String>>,
+    },
+    Ping {
+        reply: oneshot::Sender<Result<PingResult, String>>,
+    },
+    // 关闭端口并结束线程，端口释放后回复
+    Close {
+        reply: oneshot::Sender<()>,
+    },
+}
+
+// 单个端口的 I/O 工作线程句柄
+//
+// 串口读写都是阻塞操作，因此每个端口由一个专用线程独占 ServoController，
+// 异步命令只通过通道与其交互，不会阻塞 tokio 运行时。
+// 收到关闭请求或所有句柄被释放后线程退出，端口随 ServoController 一起关闭。
+// 急停标志置位期间，队列中尚未处理的请求会被直接拒绝。
+// 发生 I/O 错误时线程关闭通道并退出，句柄随之变为已关闭状态。
+#[derive(Clone)]
+pub struct ServoWorker {
+    port_name: String,
+    sender: mpsc::UnboundedSender<ServoRequest>,
+}
+
+impl ServoWorker {
+    // 在专用线程中打开端口，打开成功后返回句柄
+    pub async fn spawn(port_name: &str, config: &DeviceConfig, emergency_stop: Arc<AtomicBool>) -> Result<Self, String> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let (ready_tx, ready_rx) = oneshot::channel();
+        let thread_port_name = port_name.to_string();
+        let config = config.clone();
+
+        thread::Builder::new()
+            .name(format!("servo-io-{}", port_name))
+            .spawn(move || {
+                match ServoController::new(&thread_port_name, &config) {
+                    Ok(controller) => {
+                        let _ = ready_tx.send(Ok(()));
+                        run(thread_port_name, controller, receiver, emergency_stop);
+                    }
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(format!("Failed to create ServoController: {}", e)));
+                    }
+                }
+            })
+            .map_err(|e| format!("Failed to start I/O thread for {}: {}", port_name, e))?;
+
+        ready_rx.await
+            .map_err(|_| format!("I/O thread for {} exited during startup", port_name))??;
+
+        Ok(ServoWorker {
+            port_name: port_name.to_string(),
+            sender,
+        })
+    }
+
+    // 设置若干通道的角度，通道以 (序号, 角度) 表示
+    pub async fn set_channels(&self, channels: Vec<(u8, u8)>) -> Result<PositionAck, String> {
+        let (reply, response) = oneshot::channel();
+        self.send(ServoRequest::SetChannels { channels, reply })?;
+        response.await
+            .map_err(|_| format!("I/O thread for {} stopped before replying", self.port_name))?
+    }
+
+    // 实际打开的端口名称
+    pub fn port_name(&self) -> &str {
+        &self.port_name
+    }
+
+    // 查询设备身份
+    pub async fn identify(&self) -> Result<Identification, String> {
+        let (reply, response) = oneshot::channel();
+        self.send(ServoRequest::Identify { reply })?;
+        response.await
+            .map_err(|_| format!("I/O thread for {} stopped before replying", self.port_name))?
+    }
+
+    // 存活探测
+    pub async fn ping(&self) -> Result<PingResult, String> {
+        let (reply, response) = oneshot::channel();
+        self.send(ServoRequest::Ping { reply })?;
+        response.await
+            .map_err(|_| format!("I/O thread for {} stopped before replying", self.port_name))?
+    }
+
+    // 关闭端口，返回时端口已经释放，其他句柄之后的请求都会失败
+    pub async fn close(&self) -> Result<(), String> {
+        let (reply, response) = oneshot::channel();
+        if self.send(ServoRequest::Close { reply }).is_err() {
+            // 线程已经退出，端口早已关闭
+            return Ok(());
+        }
+        response.await
+            .map_err(|_| format!("I/O thread for {} stopped before closing the port", self.port_name))
+    }
+
+    // I/O 线程是否已经退出，退出后需要重新打开端口
+    pub fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+
+    // 两个句柄是否指向同一个 I/O 线程
+    pub fn same_worker(&self, other: &ServoWorker) -> bool {
+        self.sender.same_channel(&other.sender)
+    }
+
+    fn send(&self, request: ServoRequest) -> Result<(), String> {
+        self.sender.send(request)
+            .map_err(|_| format!("I/O thread for {} is not running", self.port_name))
+    }
+}
+
+// I/O 线程主循环，依次处理请求直到所有句柄被释放
+fn run(
+    port_name: String,
+    mut controller: ServoController,
+    mut receiver: mpsc::UnboundedReceiver<ServoRequest>,
+    emergency_stop: Arc<AtomicBool>,
+) {
+    log_message(
+        format!("I/O thread started for {}", port_name),
+        "INFO".to_string(),
+        MODEL_NAME.to_string(),
+    );
+
+    let mut close_reply = None;
+    while let Some(request) = receiver.blocking_recv() {
+        let link_lost = match request {
+            ServoRequest::SetChannels { channels, reply } => {
+                if emergency_stop.load(Ordering::SeqCst) {
+                    let _ = reply.send(Err("Emergency stop is active".to_string()));
+                    continue;
+                }
+                respond(&mut receiver, reply, controller.set_channels(&channels))
+            }
+            ServoRequest::Identify { reply } => respond(&mut receiver, reply, controller.identify()),
+            ServoRequest::Ping { reply } => respond(&mut receiver, reply, controller.ping()),
+            ServoRequest::Close { reply } => {
+                close_reply = Some(reply);
+                break;
+            }
+        };
+
+        if link_lost {
+            log_message(
+                format!("Link to {} lost, stopping I/O thread", port_name),
+                "ERROR".to_string(),
+                MODEL_NAME.to_string(),
+            );
+            break;
+        }
+    }
+
+    drop(controller);
+    log_message(
+        format!("I/O thread stopped for {}, port closed", port_name),
+        "INFO".to_string(),
+        MODEL_NAME.to_string(),
+    );
+    if let Some(reply) = close_reply {
+        let _ = reply.send(());
+    }
+}
+
+// 回复请求结果，返回链路是否已断开
+//
+// 链路断开时先关闭通道再回复，调用方收到错误时 is_closed 已经为 true
+fn respond<T>(
+    receiver: &mut mpsc::UnboundedReceiver<ServoRequest>,
+    reply: oneshot::Sender<Result<T, String>>,
+    result: Result<T, Box<dyn std::error::Error>>,
+) -> bool {
+    let link_lost = is_link_error(&result);
+    if link_lost {
+        receiver.close();
+    }
+    let _ = reply.send(result.map_err(|e| e.to_string()));
+    link_lost
+}
+
+// I/O 错误说明端口已经不可用，其他错误(例如参数无效)不影响链路
+//
+// 超时等暂时性错误与读取时一样只让本次命令失败，例如 USB 串口的发送缓冲区暂时写满
+fn is_link_error<T>(result: &Result<T, Box<dyn std::error::Error>>) -> bool {
+    let Err(e) = result else {
+        return false;
+    };
+    e.downcast_ref::<io::Error>().is_some_and(|e| {
+        !matches!(e.kind(), io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn io_error(kind: io::ErrorKind) -> Result<(), Box<dyn std::error::Error>> {
+        Err(Box::new(io::Error::new(kind, "test")))
+    }
+
+    #[test]
+    fn transient_io_errors_keep_the_link() {
+        assert!(!is_link_error(&io_error(io::ErrorKind::TimedOut)));
+        assert!(!is_link_error(&io_error(io::ErrorKind::WouldBlock)));
+        assert!(!is_link_error(&io_error(io::ErrorKind::Interrupted)));
+        assert!(is_link_error(&io_error(io::ErrorKind::BrokenPipe)));
+        assert!(is_link_error(&io_error(io::ErrorKind::UnexpectedEof)));
+        assert!(!is_link_error(&Err::<(), _>("Invalid channel".into())));
+        assert!(!is_link_error(&Ok::<(), Box<dyn std::error::Error>>(())));
+    }
+}
@@ -0,0 +1,399 @@
+// 引入必要的外部依赖
+use serialport::{DataBits, SerialPort, StopBits};
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+use once_cell::sync::Lazy;
+
+// 引入本地模块
+use crate::commands::log_message;
+use crate::device_manager::{DeviceConfig, FlowControl, Parity};
+use crate::virtual_device::{VirtualTransport, VIRTUAL_PREFIX};
+
+// 定义模块名称常量
+const MODEL_NAME: &str = "transport";
+
+// TCP 地址前缀，例如 tcp://192.168.1.20:5000
+pub const TCP_PREFIX: &str = "tcp://";
+// 内存回环前缀，例如 loopback://test
+pub const LOOPBACK_PREFIX: &str = "loopback://";
+
+// 舵机控制器使用的传输通道
+//
+// 读操作在超时后应返回 ErrorKind::TimedOut，与 serialport 的行为保持一致
+pub trait Transport: Read + Write + Send {
+    // 用于日志输出的通道描述
+    fn description(&self) -> String;
+
+    // 设置读超时时间，写超时保持连接时的配置
+    fn set_read_timeout(&mut self, timeout: Duration) -> io::Result<()>;
+
+    // 不收发数据，只检查通道是否仍然可用
+    fn check_open(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// 根据设备名称和连接参数打开对应的传输通道
+pub fn open_transport(device_name: &str, config: &DeviceConfig) -> Result<Box<dyn Transport>, Box<dyn std::error::Error>> {
+    log_message(
+        format!("Opening transport for device: {}", device_name),
+        "INFO".to_string(),
+        MODEL_NAME.to_string(),
+    );
+
+    if let Some(addr) = device_name.strip_prefix(TCP_PREFIX) {
+        Ok(Box::new(TcpTransport::connect(addr, config)?))
+    } else if device_name.starts_with(LOOPBACK_PREFIX) {
+        Ok(Box::new(LoopbackTransport::open(device_name, config)?))
+    } else if device_name.starts_with(VIRTUAL_PREFIX) {
+        Ok(Box::new(VirtualTransport::new(device_name, config)))
+    } else {
+        Ok(Box::new(SerialTransport::open(device_name, config)?))
+    }
+}
+
+// 串口传输通道
+//
+// serialport 只有一个共用的超时设置，读写前按需切换到对应的超时时间
+pub struct SerialTransport {
+    port_name: String,
+    port: Box<dyn SerialPort>,
+    read_timeout: Duration,
+    write_timeout: Duration,
+    active_timeout: Duration,
+}
+
+impl SerialTransport {
+    pub fn open(port_name: &str, config: &DeviceConfig) -> Result<Self, serialport::Error> {
+        let data_bits = match config.data_bits {
+            5 => DataBits::Five,
+            6 => DataBits::Six,
+            7 => DataBits::Seven,
+            8 => DataBits::Eight,
+            other => return Err(invalid_config(format!("Unsupported data bits: {}", other))),
+        };
+        let stop_bits = match config.stop_bits {
+            1 => StopBits::One,
+            2 => StopBits::Two,
+            other => return Err(invalid_config(format!("Unsupported stop bits: {}", other))),
+        };
+        let parity = match config.parity {
+            Parity::None => serialport::Parity::None,
+            Parity::Odd => serialport::Parity::Odd,
+            Parity::Even => serialport::Parity::Even,
+        };
+        let flow_control = match config.flow_control {
+            FlowControl::None => serialport::FlowControl::None,
+            FlowControl::Software => serialport::FlowControl::Software,
+            FlowControl::Hardware => serialport::FlowControl::Hardware,
+        };
+        let read_timeout = Duration::from_millis(config.read_timeout_ms);
+
+        let port = serialport::new(port_name, config.baud_rate)
+            .data_bits(data_bits)
+            .parity(parity)
+            .stop_bits(stop_bits)
+            .flow_control(flow_control)
+            .timeout(read_timeout)
+            .open()?;
+
+        log_message(
+            format!("Successfully opened serial port {} at {} baud", port_name, config.baud_rate),
+            "INFO".to_string(),
+            MODEL_NAME.to_string(),
+        );
+
+        Ok(SerialTransport {
+            port_name: port_name.to_string(),
+            port,
+            read_timeout,
+            write_timeout: Duration::from_millis(config.write_timeout_ms),
+            active_timeout: read_timeout,
+        })
+    }
+
+    fn use_timeout(&mut self, timeout: Duration) -> io::Result<()> {
+        if self.active_timeout != timeout {
+            self.port.set_timeout(timeout).map_err(io::Error::from)?;
+            self.active_timeout = timeout;
+        }
+        Ok(())
+    }
+}
+
+fn invalid_config(description: String) -> serialport::Error {
+    serialport::Error::new(serialport::ErrorKind::InvalidInput, description)
+}
+
+impl Read for SerialTransport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.use_timeout(self.read_timeout)?;
+        self.port.read(buf)
+    }
+}
+
+impl Write for SerialTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.use_timeout(self.write_timeout)?;
+        self.port.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.port.flush()
+    }
+}
+
+impl Transport for SerialTransport {
+    fn description(&self) -> String {
+        format!("serial:{}", self.port_name)
+    }
+
+    fn set_read_timeout(&mut self, timeout: Duration) -> io::Result<()> {
+        self.read_timeout = timeout;
+        Ok(())
+    }
+
+    // 设备被拔出后查询缓冲区会返回错误
+    fn check_open(&mut self) -> io::Result<()> {
+        self.port.bytes_to_read()?;
+        Ok(())
+    }
+}
+
+// TCP 传输通道，用于通过网络连接的控制板
+pub struct TcpTransport {
+    addr: String,
+    stream: TcpStream,
+}
+
+impl TcpTransport {
+    pub fn connect(addr: &str, config: &DeviceConfig) -> io::Result<Self> {
+        let socket_addr = addr.to_socket_addrs()?.next().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid TCP address: {}", addr))
+        })?;
+
+        let write_timeout = Duration::from_millis(config.write_timeout_ms);
+        let stream = TcpStream::connect_timeout(&socket_addr, write_timeout)?;
+        stream.set_read_timeout(Some(Duration::from_millis(config.read_timeout_ms)))?;
+        stream.set_write_timeout(Some(write_timeout))?;
+        stream.set_nodelay(true)?;
+
+        log_message(
+            format!("Successfully connected to TCP device {}", addr),
+            "INFO".to_string(),
+            MODEL_NAME.to_string(),
+        );
+
+        Ok(TcpTransport {
+            addr: addr.to_string(),
+            stream,
+        })
+    }
+}
+
+impl Read for TcpTransport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // 不同平台上 socket 超时分别返回 WouldBlock 或 TimedOut，这里统一为 TimedOut
+        self.stream.read(buf).map_err(|e| match e.kind() {
+            io::ErrorKind::WouldBlock => io::Error::new(io::ErrorKind::TimedOut, e),
+            _ => e,
+        })
+    }
+}
+
+impl Write for TcpTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+impl Transport for TcpTransport {
+    fn description(&self) -> String {
+        format!("tcp:{}", self.addr)
+    }
+
+    fn set_read_timeout(&mut self, timeout: Duration) -> io::Result<()> {
+        self.stream.set_read_timeout(Some(timeout))
+    }
+
+    fn check_open(&mut self) -> io::Result<()> {
+        match self.stream.take_error()? {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
+
+// 内存回环中一个方向的缓冲区
+#[derive(Default)]
+struct Pipe {
+    state: Mutex<PipeState>,
+    available: Condvar,
+}
+
+#[derive(Default)]
+struct PipeState {
+    buffer: VecDeque<u8>,
+    // 写入端已经释放，缓冲区读完后读操作返回 0
+    closed: bool,
+}
+
+// 等待被 open_transport 打开的回环端，按设备名称索引
+static LOOPBACK_ENDPOINTS: Lazy<Mutex<HashMap<String, LoopbackTransport>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+// 内存回环传输通道
+//
+// 直接打开时写入的数据会被原样读回；通过 listen 注册过的地址打开时与对端组成一对，
+// 双方读到的都是对方写入的数据，对端释放后读操作返回 0，与设备断开连接时的表现一致
+pub struct LoopbackTransport {
+    name: String,
+    incoming: Arc<Pipe>,
+    outgoing: Arc<Pipe>,
+    timeout: Duration,
+}
+
+impl LoopbackTransport {
+    pub fn new(name: &str, config: &DeviceConfig) -> Self {
+        let pipe = Arc::new(Pipe::default());
+        LoopbackTransport {
+            name: name.to_string(),
+            incoming: pipe.clone(),
+            outgoing: pipe,
+            timeout: Duration::from_millis(config.read_timeout_ms),
+        }
+    }
+
+    // 打开回环地址，已注册对端时取出与其配对的一端，否则回退到回显模式
+    pub fn open(name: &str, config: &DeviceConfig) -> io::Result<Self> {
+        let endpoint = LOOPBACK_ENDPOINTS.lock()
+            .map_err(|e| io::Error::other(e.to_string()))?
+            .remove(name);
+        match endpoint {
+            Some(mut endpoint) => {
+                endpoint.timeout = Duration::from_millis(config.read_timeout_ms);
+                Ok(endpoint)
+            }
+            None => Ok(LoopbackTransport::new(name, config)),
+        }
+    }
+
+    // 为回环地址注册对端并返回由调用方持有的一端，例如测试中的模拟固件
+    //
+    // 下一次打开该地址时得到另一端，之后再打开同一地址又回到回显模式
+    #[cfg(test)]
+    pub fn listen(name: &str) -> Self {
+        let to_host = Arc::new(Pipe::default());
+        let to_peer = Arc::new(Pipe::default());
+        let host = LoopbackTransport {
+            name: name.to_string(),
+            incoming: to_host.clone(),
+            outgoing: to_peer.clone(),
+            timeout: Duration::from_millis(DeviceConfig::default().read_timeout_ms),
+        };
+        LOOPBACK_ENDPOINTS.lock().unwrap().insert(name.to_string(), host);
+        LoopbackTransport {
+            name: format!("{} (peer)", name),
+            incoming: to_peer,
+            outgoing: to_host,
+            timeout: Duration::from_millis(DeviceConfig::default().read_timeout_ms),
+        }
+    }
+}
+
+impl Read for LoopbackTransport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let deadline = Instant::now() + self.timeout;
+        let mut state = self.incoming.state.lock()
+            .map_err(|e| io::Error::other(e.to_string()))?;
+
+        while state.buffer.is_empty() {
+            if state.closed {
+                return Ok(0);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(io::Error::new(io::ErrorKind::TimedOut, "Loopback read timed out"));
+            }
+            state = self.incoming.available.wait_timeout(state, deadline - now)
+                .map_err(|e| io::Error::other(e.to_string()))?
+                .0;
+        }
+
+        let count = buf.len().min(state.buffer.len());
+        for (slot, byte) in buf.iter_mut().zip(state.buffer.drain(..count)) {
+            *slot = byte;
+        }
+        Ok(count)
+    }
+}
+
+impl Write for LoopbackTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut state = self.outgoing.state.lock()
+            .map_err(|e| io::Error::other(e.to_string()))?;
+        state.buffer.extend(buf);
+        self.outgoing.available.notify_all();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for LoopbackTransport {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.outgoing.state.lock() {
+            state.closed = true;
+        }
+        self.outgoing.available.notify_all();
+    }
+}
+
+impl Transport for LoopbackTransport {
+    fn description(&self) -> String {
+        format!("loopback:{}", self.name)
+    }
+
+    fn set_read_timeout(&mut self, timeout: Duration) -> io::Result<()> {
+        self.timeout = timeout;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paired_loopback_delivers_to_the_other_end() {
+        let mut peer = LoopbackTransport::listen("loopback://pair");
+        let mut host = LoopbackTransport::open("loopback://pair", &DeviceConfig::default()).unwrap();
+
+        host.write_all(b"ping").unwrap();
+        let mut buf = [0u8; 4];
+        peer.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"ping");
+
+        // 对端释放后主机端读到 EOF
+        drop(peer);
+        assert_eq!(host.read(&mut buf).unwrap(), 0);
+
+        // 配对只使用一次，再次打开回到回显模式
+        let mut echo = LoopbackTransport::open("loopback://pair", &DeviceConfig::default()).unwrap();
+        echo.write_all(b"echo").unwrap();
+        echo.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"echo");
+    }
+}
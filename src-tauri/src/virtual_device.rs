@@ -0,0 +1,372 @@
+// 内置的虚拟舵机设备，用于开发和演示
+//
+// 模拟 arduino/initr4.ino 固件：回复握手、身份查询和 Ping，按固件的方式限幅并确认位置命令，
+// 不在帧中的字节按旧版文本协议 "x,y\n" 处理，因此两种协议的上位机都可以使用虚拟设备；
+// 舵机以固定的转速向目标角度移动，运动过程中的角度通过事件发送给前端预览
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+// 引入本地模块
+use crate::commands::log_message;
+use crate::device_manager::DeviceConfig;
+use crate::events::{self, VIRTUAL_POSE};
+use crate::protocol::{
+    Frame, FrameDecoder, FrameError, FrameKind, FRAME_START, NACK_BAD_CHECKSUM, NACK_INVALID_PAYLOAD, NACK_UNSUPPORTED,
+    PROTOCOL_VERSION,
+};
+use crate::transport::Transport;
+
+// 定义模块名称常量
+const MODEL_NAME: &str = "virtual_device";
+
+// 虚拟设备的端口名称前缀，例如 virtual:desk
+pub const VIRTUAL_PREFIX: &str = "virtual:";
+// 默认提供的虚拟设备
+pub const DEFAULT_VIRTUAL_DEVICE: &str = "virtual:desk";
+
+// 虚拟固件的版本号及型号，Identify 时报告
+const FIRMWARE_VERSION: [u8; 3] = [1, 0, 0];
+const MODEL: &str = "Desky Virtual";
+// 固件允许的角度范围，超出的命令会被限幅
+const MIN_ANGLE: u8 = 0;
+const MAX_ANGLE: u8 = 180;
+// 上电时所有舵机位于中间位置
+const CENTER_ANGLE: f64 = 90.0;
+// 舵机转速(度/秒)，与常见的 SG90 舵机相当
+const SLEW_RATE: f64 = 360.0;
+// 运动过程中发送预览事件的周期
+const POSE_INTERVAL: Duration = Duration::from_millis(33);
+// 旧版文本协议一行的最大长度，与固件一致，超出时丢弃该行
+const MAX_LINE_LEN: usize = 32;
+
+// virtual-device-pose 事件的负载
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VirtualPose {
+    pub port_name: String,
+    // 按通道序号排列的当前模拟角度
+    pub angles: Vec<f64>,
+    // 按通道序号排列的目标角度
+    pub targets: Vec<u8>,
+    pub moving: bool,
+}
+
+// 所有舵机的模拟状态，角度在读取时按经过的时间推进
+struct Simulation {
+    angles: Vec<f64>,
+    targets: Vec<u8>,
+    updated: Instant,
+}
+
+impl Simulation {
+    fn new(channel_count: usize) -> Self {
+        Simulation {
+            angles: vec![CENTER_ANGLE; channel_count],
+            targets: vec![CENTER_ANGLE as u8; channel_count],
+            updated: Instant::now(),
+        }
+    }
+
+    // 按转速把各舵机向目标移动，返回是否仍有舵机在运动
+    fn advance(&mut self) -> bool {
+        let step = SLEW_RATE * self.updated.elapsed().as_secs_f64();
+        self.updated = Instant::now();
+
+        let mut moving = false;
+        for (angle, &target) in self.angles.iter_mut().zip(&self.targets) {
+            let remaining = target as f64 - *angle;
+            if remaining.abs() <= step {
+                *angle = target as f64;
+            } else {
+                *angle += step.copysign(remaining);
+                moving = true;
+            }
+        }
+        moving
+    }
+
+    fn pose(&self, port_name: &str, moving: bool) -> VirtualPose {
+        VirtualPose {
+            port_name: port_name.to_string(),
+            angles: self.angles.clone(),
+            targets: self.targets.clone(),
+            moving,
+        }
+    }
+}
+
+// 虚拟设备的传输通道，写入的帧由虚拟固件同步处理，回复放入读缓冲区
+pub struct VirtualTransport {
+    port_name: String,
+    decoder: FrameDecoder,
+    // 正在接收的文本行，超长的行在遇到换行前一直丢弃
+    line: Vec<u8>,
+    discard_line: bool,
+    replies: VecDeque<u8>,
+    simulation: Arc<Mutex<Simulation>>,
+}
+
+impl VirtualTransport {
+    pub fn new(port_name: &str, config: &DeviceConfig) -> Self {
+        log_message(
+            format!("Starting virtual device {} with {} channel(s)", port_name, config.channels.len()),
+            "INFO".to_string(),
+            MODEL_NAME.to_string(),
+        );
+
+        let simulation = Arc::new(Mutex::new(Simulation::new(config.channels.len())));
+        spawn_pose_stream(port_name.to_string(), Arc::downgrade(&simulation));
+
+        VirtualTransport {
+            port_name: port_name.to_string(),
+            decoder: FrameDecoder::new(),
+            line: Vec::new(),
+            discard_line: false,
+            replies: VecDeque::new(),
+            simulation,
+        }
+    }
+
+    // 处理一帧并生成回复，主机发来的 ACK/NACK 不需要回复
+    fn handle(&mut self, frame: Frame) -> Option<Frame> {
+        let reply = match frame.kind {
+            FrameKind::Hello => Ok(vec![PROTOCOL_VERSION]),
+            FrameKind::Identify => {
+                let channel_count = self.lock_simulation().targets.len() as u8;
+                let mut payload = FIRMWARE_VERSION.to_vec();
+                payload.push(channel_count);
+                payload.extend_from_slice(MODEL.as_bytes());
+                Ok(payload)
+            }
+            FrameKind::Ping => Ok(Vec::new()),
+            FrameKind::SetPosition => self.set_position(&frame.payload),
+            FrameKind::Ack | FrameKind::Nack => return None,
+        };
+
+        Some(match reply {
+            Ok(payload) => Frame::new(PROTOCOL_VERSION, frame.seq, FrameKind::Ack, payload),
+            Err(code) => Frame::new(PROTOCOL_VERSION, frame.seq, FrameKind::Nack, vec![code]),
+        })
+    }
+
+    // 应用位置命令，回复限幅后实际采用的 (通道, 角度) 字节对
+    fn set_position(&mut self, payload: &[u8]) -> Result<Vec<u8>, u8> {
+        let mut simulation = self.lock_simulation();
+        let channel_count = simulation.targets.len();
+        if payload.is_empty() || !payload.len().is_multiple_of(2) {
+            return Err(NACK_INVALID_PAYLOAD);
+        }
+        if payload.chunks_exact(2).any(|pair| pair[0] as usize >= channel_count) {
+            return Err(NACK_INVALID_PAYLOAD);
+        }
+
+        // 先推进到当前时刻，新的目标从当前角度开始移动
+        simulation.advance();
+        let mut applied = Vec::with_capacity(payload.len());
+        for pair in payload.chunks_exact(2) {
+            let angle = pair[1].clamp(MIN_ANGLE, MAX_ANGLE);
+            simulation.targets[pair[0] as usize] = angle;
+            applied.extend_from_slice(&[pair[0], angle]);
+        }
+        Ok(applied)
+    }
+
+    // 处理解码器中已经完整的帧
+    fn receive_frames(&mut self) {
+        while let Some(result) = self.decoder.next_frame() {
+            let reply = match result {
+                Ok(frame) => self.handle(frame),
+                Err(FrameError::BadChecksum { seq }) => {
+                    Some(Frame::new(PROTOCOL_VERSION, seq, FrameKind::Nack, vec![NACK_BAD_CHECKSUM]))
+                }
+                Err(FrameError::UnknownKind { seq, .. }) => {
+                    Some(Frame::new(PROTOCOL_VERSION, seq, FrameKind::Nack, vec![NACK_UNSUPPORTED]))
+                }
+                // 解码器不会产生超长的帧
+                Err(FrameError::PayloadTooLong { .. }) => None,
+            };
+            if let Some(reply) = reply {
+                match reply.encode() {
+                    Ok(bytes) => self.replies.extend(bytes),
+                    Err(e) => log_message(format!("Failed to encode reply: {}", e), "ERROR".to_string(), MODEL_NAME.to_string()),
+                }
+            }
+        }
+    }
+
+    // 旧版文本协议，逐字节接收 "x,y\n"
+    fn receive_line_byte(&mut self, byte: u8) {
+        match byte {
+            b'\r' => {}
+            b'\n' => {
+                let line = std::mem::take(&mut self.line);
+                if std::mem::take(&mut self.discard_line) {
+                    return;
+                }
+                if let Some(reply) = self.handle_line(&String::from_utf8_lossy(&line)) {
+                    self.replies.extend(reply.into_bytes());
+                }
+            }
+            _ if self.line.len() < MAX_LINE_LEN => self.line.push(byte),
+            _ => {
+                self.line.clear();
+                self.discard_line = true;
+            }
+        }
+    }
+
+    // 设置前两个通道并回复 "Position set to: x,y"，无法解析的行与固件一样直接忽略
+    fn handle_line(&mut self, line: &str) -> Option<String> {
+        let (x, y) = line.trim().split_once(',')?;
+        let parse = |value: &str| value.trim().parse::<i64>().ok().map(|v| v.clamp(MIN_ANGLE as i64, MAX_ANGLE as i64) as u8);
+        let (x, y) = (parse(x)?, parse(y)?);
+
+        let mut simulation = self.lock_simulation();
+        simulation.advance();
+        for (target, angle) in simulation.targets.iter_mut().zip([x, y]) {
+            *target = angle;
+        }
+        Some(format!("Position set to: {},{}\r\n", x, y))
+    }
+
+    fn lock_simulation(&self) -> std::sync::MutexGuard<'_, Simulation> {
+        // 模拟状态只包含角度，持有锁的线程崩溃后状态依然可用
+        self.simulation.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Read for VirtualTransport {
+    // 回复在写入时就已生成，读缓冲区为空说明不会再有回复，直接按超时处理
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.replies.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "Virtual device has no pending reply"));
+        }
+
+        let count = buf.len().min(self.replies.len());
+        for (slot, byte) in buf.iter_mut().zip(self.replies.drain(..count)) {
+            *slot = byte;
+        }
+        Ok(count)
+    }
+}
+
+impl Write for VirtualTransport {
+    // 与固件一样，收到起始字节时开始接收一帧，其余字节按文本行处理
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &byte in buf {
+            if self.decoder.is_empty() && byte != FRAME_START {
+                self.receive_line_byte(byte);
+            } else {
+                self.decoder.push(&[byte]);
+                self.receive_frames();
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Transport for VirtualTransport {
+    fn description(&self) -> String {
+        self.port_name.clone()
+    }
+
+    // 回复是同步生成的，不需要等待
+    fn set_read_timeout(&mut self, _timeout: Duration) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// 后台线程在舵机运动时周期性地发送模拟角度，传输通道关闭后退出
+fn spawn_pose_stream(port_name: String, simulation: Weak<Mutex<Simulation>>) {
+    std::thread::spawn(move || {
+        // 启动后先发送一次初始角度
+        let mut was_moving = true;
+        loop {
+            std::thread::sleep(POSE_INTERVAL);
+            let Some(simulation) = simulation.upgrade() else {
+                break;
+            };
+
+            let pose = {
+                let mut simulation = simulation.lock().unwrap_or_else(|e| e.into_inner());
+                let moving = simulation.advance();
+                // 静止时不重复发送，停下后再发送一次最终角度
+                (moving || was_moving).then(|| simulation.pose(&port_name, moving))
+            };
+            if let Some(pose) = pose {
+                was_moving = pose.moving;
+                events::emit(VIRTUAL_POSE, pose);
+            }
+        }
+
+        log_message(
+            format!("Virtual device {} closed", port_name),
+            "INFO".to_string(),
+            MODEL_NAME.to_string(),
+        );
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::servo_controller::{AckStatus, Identification, ServoController};
+
+    fn read_reply(transport: &mut VirtualTransport) -> String {
+        let mut buf = [0u8; 64];
+        let n = transport.read(&mut buf).unwrap();
+        String::from_utf8_lossy(&buf[..n]).into_owned()
+    }
+
+    #[test]
+    fn legacy_lines_move_the_first_two_channels() {
+        let mut transport = VirtualTransport::new("virtual:legacy", &DeviceConfig::default());
+        transport.write_all(b"120,45\n").unwrap();
+        assert_eq!(read_reply(&mut transport), "Position set to: 120,45\r\n");
+
+        transport.write_all(b"200,-5\r\n").unwrap();
+        assert_eq!(read_reply(&mut transport), "Position set to: 180,0\r\n");
+        assert_eq!(transport.lock_simulation().targets, vec![180, 0]);
+
+        // 无法解析和超长的行不回复
+        transport.write_all(b"hello\n").unwrap();
+        transport.write_all(&[b'1'; MAX_LINE_LEN + 8]).unwrap();
+        transport.write_all(b",1\n").unwrap();
+        assert!(transport.read(&mut [0u8; 8]).is_err());
+    }
+
+    #[test]
+    fn handshake_frame_and_legacy_lines_can_share_the_stream() {
+        let mut transport = VirtualTransport::new("virtual:mixed", &DeviceConfig::default());
+        let mut bytes = Frame::new(PROTOCOL_VERSION, 7, FrameKind::Hello, vec![PROTOCOL_VERSION]).encode().unwrap();
+        bytes.extend_from_slice(b"\n10,20\n");
+        transport.write_all(&bytes).unwrap();
+
+        let mut decoder = FrameDecoder::new();
+        let mut buf = [0u8; 64];
+        let n = transport.read(&mut buf).unwrap();
+        decoder.push(&buf[..n]);
+        let reply = decoder.next_frame().unwrap().unwrap();
+        assert_eq!((reply.seq, reply.kind), (7, FrameKind::Ack));
+        assert!(String::from_utf8_lossy(&buf[..n]).ends_with("Position set to: 10,20\r\n"));
+    }
+
+    #[test]
+    fn framed_controller_negotiates_with_the_virtual_device() {
+        let config = DeviceConfig::default();
+        let transport = VirtualTransport::new("virtual:framed", &config);
+        let mut controller = ServoController::with_transport(Box::new(transport), &config).unwrap();
+        assert!(matches!(controller.identify().unwrap(), Identification::Framed(Some(_))));
+        let ack = controller.set_channels(&[(0, 200), (1, 30)]).unwrap();
+        assert_eq!(ack.status, AckStatus::Clamped);
+        assert_eq!(ack.reported, vec![(0, 180), (1, 30)]);
+    }
+}
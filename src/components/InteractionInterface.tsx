@@ -7,7 +7,7 @@ import { DebugPanel } from './DebugPanel';
 import VideoFeed from './VideoFeed';
 import { generateResponse } from '../lib/openai';
 import { generateSpeech } from '@/lib/tts';
-import { setServoPosition, initializeServo, ServoConfig, checkDeviceStatus } from '../lib/servoControl';
+import { setServoPosition, initializeServo, ServoConfig, checkDeviceStatus, connectDevice } from '../lib/servoControl';
 import { sendMessage, checkServerStatus } from '../lib/webSocketService';
 import { FaceDetectionResult } from '../types/faceDetection';
 import { Select, SelectContent, SelectItem, SelectTrigger, SelectValue } from "@/components/ui/select";
@@ -121,7 +121,12 @@ export const InteractionInterface: React.FC = () => {
         };
         setServoConfig(servoConfig);
 
-        // 检查设备状态
+        // 先打开设备端口，再检查设备状态
+        try {
+          await connectDevice(servoConfig);
+        } catch (error) {
+          logger.log(`Device ${deviceName} could not be connected: ${error}`, 'WARN', ModelName);
+        }
         const deviceCheck = await checkDeviceStatus(servoConfig);
         setDeviceStatus(deviceCheck ? 'Online' : 'Offline');
 
@@ -0,0 +1,108 @@
+import { invoke } from '@tauri-apps/api';
+
+export type ResponseType = 'auto' | 'text' | 'json' | 'bytes';
+
+export type ResponseBody =
+  | { type: 'text'; data: string }
+  | { type: 'json'; data: unknown }
+  | { type: 'bytes'; data: number[] };
+
+export interface ProxyResponse {
+  status: number;
+  ok: boolean;
+  headers: Record<string, string>;
+  url: string;
+  elapsedMs: number;
+  body: ResponseBody;
+}
+
+// 超时和重试策略，超时时间为 0 表示不限制；非幂等请求(POST、PATCH)默认不重试
+export interface RequestPolicy {
+  connectTimeoutMs: number;
+  readTimeoutMs: number;
+  totalTimeoutMs: number;
+  maxRetries: number;
+  initialBackoffMs: number;
+  maxBackoffMs: number;
+  retryNonIdempotent: boolean;
+  retryStatuses: number[];
+}
+
+export async function getRequestPolicy(): Promise<RequestPolicy> {
+  return invoke<RequestPolicy>('get_request_policy');
+}
+
+export async function setRequestPolicy(policy: RequestPolicy): Promise<void> {
+  return invoke('set_request_policy', { policy });
+}
+
+export interface ProxyRequest {
+  targetUrl: string;
+  method: string;
+  headers?: Record<string, string>;
+  body?: number[];
+  responseType?: ResponseType;
+  // 未指定的字段沿用默认策略
+  policy?: Partial<RequestPolicy>;
+}
+
+// 通过后端代理发送请求，非 2xx 的响应同样正常返回，由调用方检查 ok 和 status
+export async function proxyFetch(request: ProxyRequest): Promise<ProxyResponse> {
+  return invoke<ProxyResponse>('proxy_fetch', {
+    targetUrl: request.targetUrl,
+    method: request.method,
+    headers: request.headers,
+    body: request.body ?? [],
+    responseType: request.responseType,
+    policy: request.policy,
+  });
+}
+
+export interface TextField {
+  name: string;
+  value: string;
+}
+
+// 文件内容由 bytes 直接给出，或由后端从本地 path 读取
+export interface FilePart {
+  name: string;
+  bytes?: number[];
+  path?: string;
+  filename?: string;
+  mimeType?: string;
+}
+
+export interface MultipartRequest {
+  targetUrl: string;
+  method?: string;
+  headers?: Record<string, string>;
+  fields?: TextField[];
+  files?: FilePart[];
+  responseType?: ResponseType;
+  policy?: Partial<RequestPolicy>;
+}
+
+// 由后端构建 multipart/form-data 请求体并发送，boundary 由后端随机生成
+export async function proxyMultipart(request: MultipartRequest): Promise<ProxyResponse> {
+  return invoke<ProxyResponse>('proxy_multipart_request', {
+    targetUrl: request.targetUrl,
+    method: request.method,
+    headers: request.headers,
+    fields: request.fields ?? [],
+    files: request.files ?? [],
+    responseType: request.responseType,
+    policy: request.policy,
+  });
+}
+
+// 以文本形式读取响应体
+export function responseText(response: ProxyResponse): string {
+  switch (response.body.type) {
+    case 'text':
+      return response.body.data;
+    case 'json':
+      return JSON.stringify(response.body.data);
+    case 'bytes':
+      return new TextDecoder().decode(new Uint8Array(response.body.data));
+  }
+}
@@ -0,0 +1,111 @@
+import { invoke } from '@tauri-apps/api';
+import { listen, UnlistenFn, Event } from '@tauri-apps/api/event';
+import { logger } from '../utils/logger';
+import type { RequestPolicy } from './httpProxy';
+
+const ModelName = "HttpStream";
+
+export interface StreamChunk {
+  streamId: string;
+  data: string;
+}
+
+export interface StreamEvent {
+  streamId: string;
+  event?: string;
+  data: string;
+  id?: string;
+}
+
+export interface StreamEnd {
+  streamId: string;
+  status: number;
+  bytes: number;
+  cancelled: boolean;
+}
+
+export interface StreamError {
+  streamId: string;
+  error: string;
+}
+
+export interface StreamHandlers {
+  onChunk?: (chunk: StreamChunk) => void;
+  onEvent?: (event: StreamEvent) => void;
+  onEnd?: (end: StreamEnd) => void;
+  onError?: (error: StreamError) => void;
+}
+
+export interface StreamRequest {
+  targetUrl: string;
+  method: string;
+  headers?: Record<string, string>;
+  body?: number[];
+  // 总超时只限制到收到响应头为止，之后按读取超时检查片段间隔
+  policy?: Partial<RequestPolicy>;
+}
+
+export interface StreamHandle {
+  streamId: string;
+  cancel: () => Promise<boolean>;
+}
+
+// 开始流式请求。监听在调用前注册，流编号返回前到达的事件先缓存，拿到编号后再按顺序分发
+export async function streamRequest(request: StreamRequest, handlers: StreamHandlers): Promise<StreamHandle> {
+  let streamId: string | null = null;
+  const pending: Array<() => void> = [];
+  const unlisteners: UnlistenFn[] = [];
+
+  const cleanup = () => unlisteners.forEach((unlisten) => unlisten());
+
+  function subscribe<T extends { streamId: string }>(name: string, handler: (payload: T) => void, last = false) {
+    return listen<T>(name, (event: Event<T>) => {
+      const deliver = () => {
+        if (event.payload.streamId !== streamId) {
+          return;
+        }
+        handler(event.payload);
+        if (last) {
+          cleanup();
+        }
+      };
+      if (streamId === null) {
+        pending.push(deliver);
+      } else {
+        deliver();
+      }
+    });
+  }
+
+  unlisteners.push(
+    await subscribe<StreamChunk>('http-stream-chunk', (chunk) => handlers.onChunk?.(chunk)),
+    await subscribe<StreamEvent>('http-stream-event', (event) => handlers.onEvent?.(event)),
+    await subscribe<StreamEnd>('http-stream-end', (end) => handlers.onEnd?.(end), true),
+    await subscribe<StreamError>('http-stream-error', (error) => {
+      logger.log(`Stream ${error.streamId} failed: ${error.error}`, 'ERROR', ModelName);
+      handlers.onError?.(error);
+    }, true),
+  );
+
+  try {
+    streamId = await invoke<string>('proxy_stream_request', {
+      targetUrl: request.targetUrl,
+      method: request.method,
+      headers: request.headers,
+      body: request.body ?? [],
+      policy: request.policy,
+    });
+  } catch (error) {
+    cleanup();
+    throw error;
+  }
+
+  logger.log(`Started stream ${streamId} for ${request.targetUrl}`, 'INFO', ModelName);
+  pending.splice(0).forEach((deliver) => deliver());
+
+  const id = streamId;
+  return {
+    streamId: id,
+    cancel: () => invoke<boolean>('cancel_stream_request', { streamId: id }),
+  };
+}
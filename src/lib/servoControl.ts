@@ -1,4 +1,5 @@
 import { invoke } from '@tauri-apps/api';
+import { listen, UnlistenFn } from '@tauri-apps/api/event';
 import { logger } from '../utils/logger';
 
 const ModelName = "ServoControl";
@@ -12,6 +13,119 @@ export interface ServoConfig {
   deviceName: string;
 }
 
+export interface AttachedPort {
+  portName: string;
+  kind: 'usb' | 'pci' | 'bluetooth' | 'virtual' | 'unknown';
+  vid?: number;
+  pid?: number;
+  serialNumber?: string;
+  manufacturer?: string;
+  product?: string;
+}
+
+export interface DetachedPort {
+  portName: string;
+  wasConnected: boolean;
+}
+
+export function onDeviceAttached(handler: (port: AttachedPort) => void): Promise<UnlistenFn> {
+  return listen<AttachedPort>('device-attached', (event) => {
+    logger.log(`Device attached: ${event.payload.portName}`, 'INFO', ModelName);
+    handler(event.payload);
+  });
+}
+
+export function onDeviceDetached(handler: (port: DetachedPort) => void): Promise<UnlistenFn> {
+  return listen<DetachedPort>('device-detached', (event) => {
+    logger.log(`Device detached: ${event.payload.portName}`, 'WARN', ModelName);
+    handler(event.payload);
+  });
+}
+
+export type ConnectionState = 'connected' | 'disconnected' | 'reconnecting' | 'failed';
+
+export interface ConnectionEvent {
+  deviceName: string;
+  state: ConnectionState;
+  attempt: number;
+  retryInMs?: number;
+  error?: string;
+}
+
+export function onDeviceConnection(handler: (event: ConnectionEvent) => void): Promise<UnlistenFn> {
+  return listen<ConnectionEvent>('device-connection', (event) => {
+    logger.log(`Device ${event.payload.deviceName} is ${event.payload.state} (attempt ${event.payload.attempt})`, 'INFO', ModelName);
+    handler(event.payload);
+  });
+}
+
+export interface DeviceState {
+  deviceName: string;
+  connection: ConnectionState;
+  commanded: Record<string, number>;
+  acknowledged: Record<string, number>;
+  commands: number;
+  errors: number;
+  lastError?: string;
+  averageLatencyMs?: number;
+  uptimeMs?: number;
+  link: {
+    commands: number;
+    acked: number;
+    mismatches: number;
+    missingAcks: number;
+    quality: number;
+  };
+}
+
+export function onDeviceState(handler: (devices: DeviceState[]) => void): Promise<UnlistenFn> {
+  return listen<DeviceState[]>('device-state', (event) => handler(event.payload));
+}
+
+export interface VirtualPose {
+  portName: string;
+  angles: number[];
+  targets: number[];
+  moving: boolean;
+}
+
+export function onVirtualPose(handler: (pose: VirtualPose) => void): Promise<UnlistenFn> {
+  return listen<VirtualPose>('virtual-device-pose', (event) => handler(event.payload));
+}
+
+export async function getDeviceState(config: ServoConfig): Promise<DeviceState> {
+  return invoke<DeviceState>('get_device_state', { deviceName: config.deviceName });
+}
+
+export async function listDevices(): Promise<DeviceState[]> {
+  return invoke<DeviceState[]>('list_devices');
+}
+
+export async function connectDevice(config: ServoConfig): Promise<void> {
+  try {
+    logger.log(`Connecting device ${config.deviceName}`, 'INFO', ModelName);
+    await invoke('connect_device', { deviceName: config.deviceName });
+    logger.log(`Device ${config.deviceName} connected`, 'INFO', ModelName);
+  } catch (error) {
+    logger.log(`Failed to connect device: ${error}`, 'ERROR', ModelName);
+    throw error;
+  }
+}
+
+export async function disconnectDevice(config: ServoConfig): Promise<void> {
+  try {
+    logger.log(`Disconnecting device ${config.deviceName}`, 'INFO', ModelName);
+    await invoke('disconnect_device', { deviceName: config.deviceName });
+  } catch (error) {
+    logger.log(`Failed to disconnect device: ${error}`, 'ERROR', ModelName);
+    throw error;
+  }
+}
+
+export async function disconnectAll(): Promise<void> {
+  await invoke('disconnect_all');
+}
+
 export async function setServoPosition(position: ServoPosition, config: ServoConfig): Promise<void> {
   try {
     logger.log(`Setting servo position for device ${config.deviceName}: X=${position.x}, Y=${position.y}`, 'INFO', ModelName);
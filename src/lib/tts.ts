@@ -125,7 +125,7 @@ export async function cloneVoice_rust(params: {
     };
 
     logger.log(`Sending request to: ${requestConfig.targetUrl}`, 'DEBUG', ModelName);
-    const response = await invoke('proxy_request', requestConfig);
+    const response = await invoke('proxy_request_with_headers', requestConfig);
     
     // 添加响应内容日志
     logger.log(`Received response: ${response}`, 'DEBUG', ModelName);